@@ -1,37 +1,41 @@
-mod computer;
+use intcode::{Computer, Interruption};
 
-use computer::Computer;
-
-fn part1() -> i32 {
+fn run(noun: i64, verb: i64) -> i64 {
     let mut computer = Computer::new();
     computer.flash("input.txt");
 
-    computer.write(1, 12);
-    computer.write(2, 02);
+    computer.write(1, noun).expect("Failed to write noun");
+    computer.write(2, verb).expect("Failed to write verb");
 
-    computer.run();
+    loop {
+        match computer.run() {
+            Interruption::Output => (),
+            Interruption::Input => panic!("computer is asking for input but none was given"),
+            Interruption::Halt => break,
+            Interruption::Fault(fault) => panic!("computer faulted: {}", fault),
+            Interruption::BudgetExhausted => unreachable!(),
+        }
+    }
 
     computer.read(0)
 }
 
-fn part2() -> Result<i32, ()> {
+fn part1() -> i64 {
+    run(12, 2)
+}
+
+fn part2() -> Result<i64, ()> {
     let target = 19690720;
-    let mut computer = Computer::new();
 
     for noun in 0..99 {
         for verb in 0..99 {
-            computer.flash("input.txt");
-            computer.write(1, noun);
-            computer.write(2, verb);
-            computer.run();
-
-            if computer.read(0) == target {
+            if run(noun, verb) == target {
                 return Result::Ok(noun * 100 + verb)
             }
         }
     }
 
-    return Result::Err(())
+    Result::Err(())
 }
 
 fn main() {
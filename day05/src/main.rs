@@ -1,9 +1,17 @@
-mod computer;
-
-use computer::Computer;
+use intcode::{Computer, Interruption, NumberInput, NumberOutput};
+use std::io;
 
 fn main() {
     let mut computer = Computer::new();
     computer.flash("input.txt");
-    computer.run();
+
+    computer.set_input_source(Box::new(NumberInput::new(io::BufReader::new(io::stdin()))));
+    computer.set_output_sink(Box::new(NumberOutput(io::stdout())));
+
+    match computer.run() {
+        Interruption::Halt => (),
+        Interruption::Input => panic!("computer is asking for input but none was given"),
+        Interruption::Fault(fault) => panic!("computer faulted: {}", fault),
+        interruption => panic!("unexpected interruption: {:?}", interruption),
+    }
 }
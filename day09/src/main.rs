@@ -1,7 +1,5 @@
-mod computer;
-
 use std::env;
-use computer::{Computer, Interruption};
+use intcode::{Computer, Interruption};
 
 #[allow(dead_code)]
 fn part1(filename: &str) {
@@ -16,7 +14,10 @@ fn part1(filename: &str) {
     loop {
         match computer.run() {
             Interruption::Output => println!("{}", computer.output().expect("Failed to retrieve output from computer")),
-            Interruption::Halt => break
+            Interruption::Input => panic!("computer is asking for input but none was given"),
+            Interruption::Halt => break,
+            Interruption::Fault(fault) => panic!("computer faulted: {}", fault),
+            Interruption::BudgetExhausted => unreachable!(),
         }
     }
 
@@ -36,7 +37,10 @@ fn part2(filename: &str) {
     loop {
         match computer.run() {
             Interruption::Output => println!("{}", computer.output().expect("Failed to retrieve output from computer")),
-            Interruption::Halt => break
+            Interruption::Input => panic!("computer is asking for input but none was given"),
+            Interruption::Halt => break,
+            Interruption::Fault(fault) => panic!("computer faulted: {}", fault),
+            Interruption::BudgetExhausted => unreachable!(),
         }
     }
 
@@ -2,7 +2,6 @@ mod color;
 mod grid;
 mod point;
 mod robot;
-mod computer;
 
 use crate::robot::Robot;
 use std::env;
@@ -1,7 +1,7 @@
 use std::fmt;
 
 use crate::color::Color;
-use crate::computer::{Computer, Interruption};
+use intcode::{Computer, Interruption};
 use crate::grid::Grid;
 use crate::point::Point;
 
@@ -108,7 +108,14 @@ impl Robot {
                         painted = false;
                     }
                 }
+                Interruption::Input => {
+                    panic!("computer is asking for input but none was given");
+                }
                 Interruption::Halt => break,
+                Interruption::Fault(fault) => {
+                    panic!("computer faulted: {}", fault);
+                }
+                Interruption::BudgetExhausted => unreachable!(),
             }
         }
     }
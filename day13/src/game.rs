@@ -1,6 +1,6 @@
-use crate::computer::{Computer, Interruption};
 use crate::grid::Grid;
 use crate::tile::Tile;
+use intcode::{Computer, Interruption};
 
 pub struct Game {
   grid: Grid,
@@ -28,10 +28,20 @@ impl Game {
 
   pub fn load(&mut self, filename: &str) {
     self.computer.flash(filename);
+
+    if let Some(tracer) = intcode::tracer_from_env() {
+      self.computer.set_tracer(tracer);
+    }
+    if let Some(recorder) = intcode::recorder_from_env() {
+      self.computer.set_recorder(recorder);
+    }
   }
 
   pub fn insert_quarters(&mut self, count: usize) {
-    self.computer.write(0, count as i64);
+    self
+      .computer
+      .write(0, count as i64)
+      .expect("Failed to insert quarters");
   }
 
   pub fn step(&mut self, input: i32) {
@@ -76,6 +86,10 @@ impl Game {
           self.over = true;
           break;
         }
+        Interruption::Fault(fault) => {
+          panic!("computer faulted: {}", fault);
+        }
+        Interruption::BudgetExhausted => unreachable!(),
       }
     }
   }
@@ -1,5 +1,4 @@
 extern crate minifb;
-mod computer;
 mod game;
 mod grid;
 mod tile;
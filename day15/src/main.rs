@@ -1,4 +1,3 @@
-mod computer;
 mod grid;
 mod point;
 mod robot;
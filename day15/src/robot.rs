@@ -1,7 +1,7 @@
-use crate::computer::{Computer, Interruption};
 use crate::grid::Grid;
 use crate::point::Point;
 use crate::tile::Tile;
+use intcode::{Computer, Interruption};
 use std::collections::VecDeque;
 
 #[derive(Debug, Eq, PartialEq, Copy, Clone)]
@@ -85,6 +85,13 @@ impl Robot {
 
   pub fn load(&mut self, program: &str) {
     self.computer.flash(program);
+
+    if let Some(tracer) = intcode::tracer_from_env() {
+      self.computer.set_tracer(tracer);
+    }
+    if let Some(recorder) = intcode::recorder_from_env() {
+      self.computer.set_recorder(recorder);
+    }
   }
 
   pub fn grid(&self) -> &Grid {
@@ -192,6 +199,10 @@ impl Robot {
         Interruption::Halt => {
           panic!("computer halted");
         }
+        Interruption::Fault(fault) => {
+          panic!("computer faulted: {}", fault);
+        }
+        Interruption::BudgetExhausted => unreachable!(),
       }
     }
   }
@@ -1,7 +1,7 @@
-use crate::computer::{Computer, Interruption};
 use crate::grid::Grid;
 use crate::point::Point;
 use crate::tile::Tile;
+use intcode::{Ascii, Computer, Interruption};
 
 pub struct Camera {
   computer: Computer,
@@ -24,35 +24,30 @@ impl Camera {
     let mut current_position = Point { x: 0, y: 0 };
 
     loop {
-      match self.computer.run() {
-        Interruption::Output => {
-          let output = self
-            .computer
-            .output()
-            .expect("Failed to retrieve status from computer");
-
-          match output {
-            10 => {
-              // line break
-              current_position.x = 0;
-              current_position.y += 1;
-            }
-            46 => {
-              // empty space
-              current_position.x += 1;
-            }
-            n => {
-              self.grid.set(current_position, Tile::from_code(n as u32));
-              current_position.x += 1;
+      match self.computer.read_line() {
+        Ok(Ascii::Text(line)) => {
+          for c in line.chars() {
+            // '.' is empty space
+            if c != '.' {
+              self.grid.set(current_position, Tile::from_code(c as u32));
             }
+            current_position.x += 1;
           }
+
+          current_position.x = 0;
+          current_position.y += 1;
         }
-        Interruption::Input => {
+        Ok(Ascii::Value(value)) => panic!("unexpected output from camera: {}", value),
+        Err(Interruption::Input) => {
           panic!("computer is asking for input but none was given");
         }
-        Interruption::Halt => {
+        Err(Interruption::Halt) => {
           return;
         }
+        Err(Interruption::Fault(fault)) => {
+          panic!("computer faulted: {}", fault);
+        }
+        Err(interruption) => panic!("unexpected interruption: {:?}", interruption),
       }
     }
   }
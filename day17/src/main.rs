@@ -1,6 +1,5 @@
 mod camera;
 mod compressor;
-mod computer;
 mod grid;
 mod path;
 mod point;
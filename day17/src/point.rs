@@ -1,6 +1,7 @@
 use std::fmt;
 use std::ops;
 
+#[allow(dead_code)]
 trait BoundaryCheck {
   fn is_box_bounded(&self, aa: &Self, bb: &Self) -> bool;
 }
@@ -1,5 +1,5 @@
-use crate::computer::{Computer, Interruption};
 use crate::path::Step;
+use intcode::{Ascii, Computer, Interruption};
 
 pub struct Robot {
   computer: Computer,
@@ -9,48 +9,36 @@ impl Robot {
   pub fn new(program: &str) -> Self {
     let mut computer = Computer::new();
     computer.flash(program);
-    computer.write(0, 2);
+
+    if let Some(recorder) = intcode::recorder_from_env() {
+      computer.set_recorder(recorder);
+    }
+    computer.write(0, 2).expect("Failed to wake up the robot");
+
     Self { computer }
   }
 
   pub fn run(&mut self, routines: &Vec<Vec<Step>>) -> i64 {
     for routine in routines.iter() {
-      for idx in 0..routine.len() {
-        for &b in routine[idx].to_string().as_bytes() {
-          self.computer.input(b as i64);
-        }
-
-        if idx < routine.len() - 1 {
-          self.computer.input(44); // comma
-        }
-      }
-
-      self.computer.input(10); // new line
+      let steps: Vec<String> = routine.iter().map(|step| step.to_string()).collect();
+      self.computer.send_line(&steps.join(","));
     }
 
     // answer to coninuous video feed
-    self.computer.input('n' as i64);
-    self.computer.input(10); // new line
+    self.computer.send_line("n");
 
-    let mut result: Vec<u8> = Vec::new();
+    let mut last_line = String::new();
 
     loop {
-      match self.computer.run() {
-        Interruption::Output => {
-          let output = self
-            .computer
-            .output()
-            .expect("Failed to retrieve status from computer");
-
-          result.push(output as u8);
-        }
-        Interruption::Input => {
+      match self.computer.read_line() {
+        Ok(Ascii::Text(line)) => last_line = line,
+        Ok(Ascii::Value(dust)) => return dust,
+        Err(Interruption::Input) => {
           panic!("computer is asking for input but none was given");
         }
-        Interruption::Halt => {
-          println!("result = {:?}", result);
-          return String::from_utf8(result).unwrap().parse().unwrap();
-        }
+        Err(Interruption::Halt) => panic!("computer halted: {}", last_line),
+        Err(Interruption::Fault(fault)) => panic!("computer faulted: {}", fault),
+        Err(interruption) => panic!("unexpected interruption: {:?}", interruption),
       }
     }
   }
@@ -0,0 +1,75 @@
+use std::env;
+use std::fs;
+use std::path::Path;
+
+// Compiles the programs below to Rust ahead of time, one module each.
+fn main() {
+  let out_dir = env::var("OUT_DIR").expect("Failed to read OUT_DIR");
+
+  let mut programs = vec![
+    ("boost", load("../day09/input.txt")),
+    // breakout with free play on, patched in before compiling
+    ("breakout", load("../day13/input.txt")),
+  ];
+  programs[1].1[0] = 2;
+
+  // the puzzle examples are compiled from `intcode::EXAMPLES` below
+  programs.push(("fault", load("programs/fault.txt")));
+
+  for (name, program) in programs.iter() {
+    write(&out_dir, name, &intcode::transpile(program));
+  }
+
+  // every conformance example's program, and a function picking the right
+  // one by its source, as a `Compiled` so tests can see whether it fell back
+  // to the interpreter
+  let mut sources: Vec<&str> = intcode::EXAMPLES
+    .iter()
+    .map(|example| example.program)
+    .collect();
+  sources.sort_unstable();
+  sources.dedup();
+
+  let mut modules = String::new();
+  let mut arms = String::new();
+  for (index, source) in sources.iter().enumerate() {
+    let name = format!("example{}", index);
+    write(
+      &out_dir,
+      &name,
+      &intcode::transpile(&intcode::parse(source)),
+    );
+
+    modules += &format!(
+      "#[allow(clippy::all, unused)]\nmod {0} {{\n  include!(concat!(env!(\"OUT_DIR\"), \"/{0}.rs\"));\n}}\n\n\
+       impl crate::Compiled for {0}::Program {{\n  \
+         fn is_interpreted(&self) -> bool {{\n    \
+           {0}::Program::is_interpreted(self)\n  \
+         }}\n}}\n\n",
+      name
+    );
+    arms += &format!("    {:?} => Box::new({}::Program::new()),\n", source, name);
+  }
+
+  write(
+    &out_dir,
+    "conformance",
+    &format!(
+      "{}// The compiled program of the conformance example with this source.\n\
+       pub fn compiled(program: &str) -> Box<dyn crate::Compiled> {{\n  \
+         match program {{\n{}    _ => panic!(\"No compiled program for {{}}\", program),\n  }}\n}}\n",
+      modules, arms
+    ),
+  );
+}
+
+fn write(out_dir: &str, name: &str, source: &str) {
+  let path = Path::new(out_dir).join(format!("{}.rs", name));
+  fs::write(path, source).expect("Failed to write compiled program");
+}
+
+fn load(filename: &str) -> Vec<i64> {
+  println!("cargo:rerun-if-changed={}", filename);
+  let raw = fs::read_to_string(filename).expect("Failed to read input file");
+  intcode::parse(&raw)
+}
@@ -0,0 +1,125 @@
+use intcode::{Computer, Engine, Interruption, Machine};
+use intcode_aot::{boost, breakout};
+use std::time::{Duration, Instant};
+
+const ROUNDS: usize = 5;
+
+const BOOST: &str = include_str!("../../../day09/input.txt");
+const BREAKOUT: &str = include_str!("../../../day13/input.txt");
+
+// Runs BOOST in sensor boost mode, returning its outputs.
+fn run_boost<M: Machine>(mut machine: M) -> Vec<i64> {
+  machine.input(2);
+
+  let mut outputs = Vec::new();
+  loop {
+    match machine.run() {
+      Interruption::Output => outputs.push(machine.output().unwrap()),
+      Interruption::Halt => return outputs,
+      interruption => panic!("unexpected interruption: {:?}", interruption),
+    }
+  }
+}
+
+// Plays the breakout game to the end by keeping the paddle under the ball,
+// returning the final score.
+fn run_breakout<M: Machine>(mut machine: M) -> Vec<i64> {
+  let (mut ball, mut paddle, mut score) = (0, 0, 0);
+  let mut pending = Vec::new();
+
+  loop {
+    match machine.run() {
+      Interruption::Output => {
+        pending.push(machine.output().unwrap());
+        if let [x, y, tile] = pending[..] {
+          match (x, y, tile) {
+            (-1, 0, value) => score = value,
+            (x, _, 3) => paddle = x,
+            (x, _, 4) => ball = x,
+            _ => (),
+          }
+          pending.clear();
+        }
+      }
+      Interruption::Input => machine.input((ball - paddle).signum()),
+      Interruption::Halt => return vec![score],
+      interruption => panic!("unexpected interruption: {:?}", interruption),
+    }
+  }
+}
+
+fn interpreter(program: &str, engine: Engine, free_play: bool) -> Computer {
+  let mut computer = Computer::new();
+  computer.set_engine(engine);
+  computer.load(program);
+  if free_play {
+    computer.write(0, 2).expect("Failed to insert quarters");
+  }
+  computer
+}
+
+fn measure<F: FnMut() -> Vec<i64>>(mut workload: F) -> (Duration, Vec<i64>) {
+  let mut best = Duration::from_secs(u64::MAX);
+  let mut result = Vec::new();
+
+  for _ in 0..ROUNDS {
+    let start = Instant::now();
+    result = workload();
+    best = best.min(start.elapsed());
+  }
+
+  (best, result)
+}
+
+fn report(name: &str, timings: &[(&str, (Duration, Vec<i64>))]) {
+  println!("{}", name);
+
+  let (baseline, expected) = &timings[0].1;
+  for (engine, (time, result)) in timings.iter() {
+    assert_eq!(result, expected, "{} disagrees on {}", engine, name);
+    println!(
+      "  {:<11} {:>10.3} ms  {:.2}x",
+      engine,
+      time.as_secs_f64() * 1000.0,
+      baseline.as_secs_f64() / time.as_secs_f64()
+    );
+  }
+}
+
+// Compares the interpreter, the cached engine and the programs compiled by
+// build.rs. Breakout writes into its own code, so its compiled version runs
+// natively only until the first such write.
+fn main() {
+  report(
+    "boost",
+    &[
+      (
+        "interpreter",
+        measure(|| run_boost(interpreter(BOOST, Engine::Interpreter, false))),
+      ),
+      (
+        "cached",
+        measure(|| run_boost(interpreter(BOOST, Engine::Cached, false))),
+      ),
+      ("compiled", measure(|| run_boost(boost::Program::new()))),
+    ],
+  );
+
+  report(
+    "breakout",
+    &[
+      (
+        "interpreter",
+        measure(|| run_breakout(interpreter(BREAKOUT, Engine::Interpreter, true))),
+      ),
+      (
+        "cached",
+        measure(|| run_breakout(interpreter(BREAKOUT, Engine::Cached, true))),
+      ),
+      (
+        "compiled",
+        measure(|| run_breakout(breakout::Program::new())),
+      ),
+    ],
+  );
+}
@@ -0,0 +1,29 @@
+// Intcode programs compiled to Rust by `intcode::transpile` at build time
+// (see build.rs). Each module defines a `Program` implementing `Machine`.
+
+macro_rules! compiled {
+  ($($name:ident),*) => {
+    $(
+      #[allow(clippy::all, unused)]
+      pub mod $name {
+        include!(concat!(env!("OUT_DIR"), "/", stringify!($name), ".rs"));
+      }
+    )*
+  };
+}
+
+compiled!(boost, breakout, fault);
+
+// A compiled program, which can tell whether it has handed over to the
+// interpreter.
+pub trait Compiled: intcode::Machine {
+  fn is_interpreted(&self) -> bool;
+}
+
+// Every program of `intcode::EXAMPLES`, compiled.
+pub mod conformance {
+  include!(concat!(env!("OUT_DIR"), "/conformance.rs"));
+}
+
+#[cfg(test)]
+mod tests;
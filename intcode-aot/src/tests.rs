@@ -0,0 +1,156 @@
+use crate::*;
+use intcode::{Computer, Example, Interruption, Machine, EXAMPLES};
+
+// Feeds `inputs` one at a time as the machine asks for them and records
+// everything it does, until it halts, faults or wants more input.
+fn session<M: Machine + ?Sized>(machine: &mut M, inputs: &[i64]) -> Vec<String> {
+  let mut inputs = inputs.iter();
+  let mut events = Vec::new();
+
+  loop {
+    match machine.run() {
+      Interruption::Output => events.push(format!("output {}", machine.output().unwrap())),
+      Interruption::Input => match inputs.next() {
+        Some(&value) => machine.input(value),
+        None => {
+          events.push("input".to_string());
+          break;
+        }
+      },
+      interruption => {
+        events.push(format!("{:?}", interruption));
+        break;
+      }
+    }
+  }
+
+  events.push(format!(
+    "{:?} after {}",
+    machine.get_state(),
+    machine.executed()
+  ));
+  events
+}
+
+fn example(name: &str) -> &'static Example {
+  EXAMPLES
+    .iter()
+    .find(|example| example.name == name)
+    .expect("No such example")
+}
+
+fn interpreted(program: &str, inputs: &[i64]) -> Vec<String> {
+  let mut computer = Computer::new();
+  computer.load(program);
+  session(&mut computer, inputs)
+}
+
+#[test]
+fn test_compiled_programs_match_interpreter() {
+  // every example with its own inputs, with none, and with a few others
+  let mut sets: Vec<Vec<i64>> = vec![Vec::new()];
+  sets.extend((6..11).map(|input| vec![input]));
+
+  for example in EXAMPLES.iter() {
+    for inputs in sets.iter().chain(std::iter::once(&example.inputs.to_vec())) {
+      let mut program = conformance::compiled(example.program);
+      assert_eq!(
+        session(&mut *program, inputs),
+        interpreted(example.program, inputs),
+        "{} with inputs {:?}",
+        example.name,
+        inputs
+      );
+    }
+  }
+
+  // programs that leave their code alone run compiled all the way through
+  for name in [
+    "day05 below 8",
+    "day05 equal to 8",
+    "day05 above 8",
+    "day09 quine",
+    "day09 16-digit number",
+    "day09 large number",
+  ]
+  .iter()
+  {
+    let example = example(name);
+    let mut program = conformance::compiled(example.program);
+    session(&mut *program, example.inputs);
+    assert!(!program.is_interpreted(), "{}", name);
+  }
+
+  // faults come from the interpreter, so they read the same
+  let mut program = fault::Program::new();
+  assert_eq!(
+    session(&mut program, &[]),
+    interpreted(include_str!("../programs/fault.txt"), &[])
+  );
+
+  let boost = include_str!("../../day09/input.txt");
+  for input in 1..3 {
+    let mut program = boost::Program::new();
+    assert_eq!(
+      session(&mut program, &[input]),
+      interpreted(boost, &[input])
+    );
+    assert!(!program.is_interpreted());
+  }
+}
+
+#[test]
+fn test_compiled_program_falls_back_on_code_writes() {
+  let walkthrough = example("day02 walkthrough").program;
+  let mut program = conformance::compiled(walkthrough);
+  assert_eq!(session(&mut *program, &[]), interpreted(walkthrough, &[]));
+  assert!(program.is_interpreted());
+  assert_eq!(program.read(0), 3500);
+
+  // so does the host writing into the code
+  let mut program = conformance::compiled(example("day09 quine").program);
+  program.write(1, 0).expect("Failed to write");
+  program.run();
+  assert!(program.is_interpreted());
+}
+
+#[test]
+fn test_compiled_breakout() {
+  fn play<M: Machine>(machine: &mut M) -> (i64, u64) {
+    let (mut ball, mut paddle, mut score) = (0, 0, 0);
+    let mut pending = Vec::new();
+
+    loop {
+      match machine.run() {
+        Interruption::Output => {
+          pending.push(machine.output().unwrap());
+          if let [x, y, tile] = pending[..] {
+            match (x, y, tile) {
+              (-1, 0, value) => score = value,
+              (x, _, 3) => paddle = x,
+              (x, _, 4) => ball = x,
+              _ => (),
+            }
+            pending.clear();
+          }
+        }
+        Interruption::Input => machine.input((ball - paddle).signum()),
+        Interruption::Halt => return (score, machine.executed()),
+        interruption => panic!("unexpected interruption: {:?}", interruption),
+      }
+    }
+  }
+
+  let mut computer = Computer::new();
+  computer.load(include_str!("../../day13/input.txt"));
+  computer.write(0, 2).expect("Failed to insert quarters");
+
+  let mut program = breakout::Program::new();
+  assert_eq!(play(&mut program), play(&mut computer));
+}
+
+#[test]
+fn test_compiled_conformance() {
+  let failures = intcode::check_conformance(|example| conformance::compiled(example.program));
+  assert!(failures.is_empty(), "{:?}", failures);
+}
@@ -0,0 +1,91 @@
+use crate::computer::{Computer, Interruption};
+
+const NEWLINE: i64 = 10;
+
+// Whether an output is a character rather than an integer result. Programs
+// that talk in ASCII report their final answers as values past this range.
+pub fn is_ascii(value: i64) -> bool {
+  (0..128).contains(&value)
+}
+
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub enum Ascii {
+  // a line of text, without its newline
+  Text(String),
+  // an output outside the ASCII range
+  Value(i64),
+}
+
+fn to_text(values: &[i64]) -> String {
+  values.iter().map(|&value| value as u8 as char).collect()
+}
+
+impl Computer {
+  // Sends `line` as ASCII input, followed by a newline.
+  pub fn send_line(&mut self, line: &str) {
+    for byte in line.bytes() {
+      self.input(byte as i64);
+    }
+    self.input(NEWLINE);
+  }
+
+  // Runs until the program has printed a whole line or output a non-ASCII
+  // value. Text at the end of the output when the program halts counts as a
+  // line too. Any other interruption is returned as an error, leaving
+  // whatever was printed so far in the output buffer.
+  pub fn read_line(&mut self) -> Result<Ascii, Interruption> {
+    loop {
+      let buffered = self.pending_output();
+      let end = buffered
+        .iter()
+        .position(|&value| value == NEWLINE || !is_ascii(value));
+
+      match end {
+        Some(0) if buffered[0] != NEWLINE => return Ok(Ascii::Value(self.take_output(1)[0])),
+        Some(end) if buffered[end] == NEWLINE => {
+          let line = self.take_output(end + 1);
+          return Ok(Ascii::Text(to_text(&line[..end])));
+        }
+        // text cut short by a value: the text comes out first
+        Some(end) => return Ok(Ascii::Text(to_text(&self.take_output(end)))),
+        None => (),
+      }
+
+      match self.run() {
+        Interruption::Output => (),
+        Interruption::Halt if !self.pending_output().is_empty() => {
+          let count = self.pending_output().len();
+          return Ok(Ascii::Text(to_text(&self.take_output(count))));
+        }
+        interruption => return Err(interruption),
+      }
+    }
+  }
+
+  // Runs until the program asks for input, returning everything it printed
+  // on the way; the prompt is the last line. If the program outputs a
+  // non-ASCII value, halts or faults instead, that's returned as an error
+  // and nothing is taken from the output buffer.
+  pub fn read_until_prompt(&mut self) -> Result<String, Interruption> {
+    loop {
+      match self.run() {
+        Interruption::Output => {
+          if !self.pending_output().iter().all(|&value| is_ascii(value)) {
+            return Err(Interruption::Output);
+          }
+        }
+        Interruption::Input => {
+          let count = self.pending_output().len();
+          return Ok(to_text(&self.take_output(count)));
+        }
+        interruption => return Err(interruption),
+      }
+    }
+  }
+
+  fn take_output(&mut self, count: usize) -> Vec<i64> {
+    (0..count)
+      .map(|_| self.output().expect("Output buffer ran out"))
+      .collect()
+  }
+}
@@ -0,0 +1,457 @@
+use crate::computer::MEMORY_LIMIT;
+use crate::instruction::{Instruction, Opcode, Parameter};
+use std::collections::HashMap;
+use std::fmt;
+
+const MAX_MACRO_DEPTH: usize = 16;
+
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct AssembleError {
+  pub line: usize,
+  pub message: String,
+}
+
+impl fmt::Display for AssembleError {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    write!(f, "line {}: {}", self.line, self.message)
+  }
+}
+
+fn error<T>(line: usize, message: String) -> Result<T, AssembleError> {
+  Err(AssembleError { line, message })
+}
+
+#[derive(Debug, Clone)]
+struct Macro {
+  params: Vec<String>,
+  body: Vec<(usize, String)>,
+}
+
+#[derive(Debug, Clone)]
+enum Operand {
+  Position(String),
+  Immediate(String),
+  Relative(String),
+}
+
+#[derive(Debug, Clone)]
+enum Statement {
+  Instruction(Opcode, Vec<Operand>),
+  Data(Vec<String>),
+  Space(usize),
+}
+
+// Assembles the text format printed by the disassembler back into a program.
+//
+//   ; comments run until the end of the line
+//   loop:                       labels name the address of the next word
+//     in -> [rb+0]              operands are #imm, [addr] and [rb+off]/[fp+off]
+//     jt [rb+0], #loop          labels can be used wherever a number can
+//     data 1, 2, end-1          data emits words verbatim
+//     space 3                   space emits zeroed words
+//   macro push value            macros substitute their parameters by name,
+//     add value, #0 -> [rb+0]   and `@` by a number unique to each expansion
+//     arb #1
+//   endm
+pub fn assemble(source: &str) -> Result<Vec<i64>, AssembleError> {
+  let mut assembler = Assembler {
+    macros: HashMap::new(),
+    labels: HashMap::new(),
+    statements: Vec::new(),
+    address: 0,
+    expansions: 0,
+  };
+
+  let lines: Vec<(usize, String)> = source
+    .lines()
+    .enumerate()
+    .map(|(i, line)| (i + 1, line.to_string()))
+    .collect();
+
+  assembler.process(&lines, 0)?;
+  assembler.emit()
+}
+
+pub fn format_program(words: &[i64]) -> String {
+  let words: Vec<String> = words.iter().map(|w| w.to_string()).collect();
+  words.join(",")
+}
+
+struct Assembler {
+  macros: HashMap<String, Macro>,
+  labels: HashMap<String, i64>,
+  statements: Vec<(usize, Statement)>,
+  address: i64,
+  expansions: usize,
+}
+
+impl Assembler {
+  fn process(&mut self, lines: &[(usize, String)], depth: usize) -> Result<(), AssembleError> {
+    let mut i = 0;
+
+    while i < lines.len() {
+      let (number, ref line) = lines[i];
+      let mut text = strip_comment(line).trim();
+      i += 1;
+
+      // leading labels
+      while let Some(colon) = text.find(':') {
+        let name = text[..colon].trim();
+        if !is_identifier(name) {
+          return error(number, format!("invalid label name '{}'", name));
+        }
+        if self.labels.insert(name.to_string(), self.address).is_some() {
+          return error(number, format!("label '{}' defined twice", name));
+        }
+        text = text[colon + 1..].trim();
+      }
+
+      if text.is_empty() {
+        continue;
+      }
+
+      let (keyword, rest) = match text.find(char::is_whitespace) {
+        Some(space) => (&text[..space], text[space..].trim()),
+        None => (text, ""),
+      };
+
+      if keyword == "macro" {
+        let (name, params) = match rest.find(char::is_whitespace) {
+          Some(space) => (&rest[..space], split_list(&rest[space..])),
+          None => (rest, Vec::new()),
+        };
+        if !is_identifier(name) {
+          return error(number, format!("invalid macro name '{}'", name));
+        }
+
+        let mut body = Vec::new();
+        loop {
+          if i >= lines.len() {
+            return error(number, format!("macro '{}' is missing its endm", name));
+          }
+          let (body_number, ref body_line) = lines[i];
+          i += 1;
+          if strip_comment(body_line).trim() == "endm" {
+            break;
+          }
+          body.push((body_number, body_line.clone()));
+        }
+
+        self.macros.insert(name.to_string(), Macro { params, body });
+      } else if let Some(definition) = self.macros.get(keyword).cloned() {
+        if depth >= MAX_MACRO_DEPTH {
+          return error(number, format!("macro '{}' nested too deeply", keyword));
+        }
+
+        let args = split_list(rest);
+        if args.len() != definition.params.len() {
+          return error(
+            number,
+            format!(
+              "macro '{}' takes {} arguments but {} were given",
+              keyword,
+              definition.params.len(),
+              args.len()
+            ),
+          );
+        }
+
+        self.expansions += 1;
+        let unique = self.expansions.to_string();
+        let expanded: Vec<(usize, String)> = definition
+          .body
+          .iter()
+          .map(|(_, body_line)| {
+            (
+              number,
+              substitute(body_line, &definition.params, &args, &unique),
+            )
+          })
+          .collect();
+
+        self.process(&expanded, depth + 1)?;
+      } else {
+        let statement = parse_statement(number, keyword, rest)?;
+        if let Statement::Space(count) = statement {
+          if (self.address as u64).saturating_add(count as u64) >= MEMORY_LIMIT {
+            return error(
+              number,
+              format!("space {} runs past the end of memory", count),
+            );
+          }
+        }
+        self.address += match statement {
+          Statement::Instruction(opcode, _) => opcode.arity() as i64 + 1,
+          Statement::Data(ref values) => values.len() as i64,
+          Statement::Space(count) => count as i64,
+        };
+        self.statements.push((number, statement));
+      }
+    }
+
+    Ok(())
+  }
+
+  fn emit(&self) -> Result<Vec<i64>, AssembleError> {
+    let mut words = Vec::new();
+
+    for (number, statement) in self.statements.iter() {
+      match statement {
+        Statement::Instruction(opcode, operands) => {
+          let mut params = Vec::with_capacity(operands.len());
+          for operand in operands.iter() {
+            params.push(match operand {
+              Operand::Position(expr) => Parameter::Position(self.evaluate(*number, expr)?),
+              Operand::Immediate(expr) => Parameter::Immediate(self.evaluate(*number, expr)?),
+              Operand::Relative(expr) => Parameter::Relative(self.evaluate(*number, expr)?),
+            });
+          }
+
+          let instruction = Instruction {
+            opcode: *opcode,
+            params,
+          };
+          words.extend(instruction.encode());
+        }
+        Statement::Data(values) => {
+          for value in values.iter() {
+            words.push(self.evaluate(*number, value)?);
+          }
+        }
+        Statement::Space(count) => words.extend(vec![0; *count]),
+      }
+    }
+
+    Ok(words)
+  }
+
+  // Sums of signed numbers and label names, e.g. `loop`, `-3`, `end-1`.
+  fn evaluate(&self, line: usize, expr: &str) -> Result<i64, AssembleError> {
+    let expr = expr.trim();
+    if expr.is_empty() {
+      return error(line, String::from("missing value"));
+    }
+
+    let mut total: i64 = 0;
+    let mut negative = false;
+    // whether the last token was a term, so the next one has to be an operator
+    let mut after_term = false;
+
+    for token in tokenize(expr) {
+      match token {
+        "+" | "-" if after_term => {
+          negative = token == "-";
+          after_term = false;
+        }
+        "+" => (),
+        "-" => negative = !negative,
+        term if after_term => {
+          return error(line, format!("missing operator before '{}'", term));
+        }
+        term => {
+          total = match total.checked_add(self.term(line, term, negative)?) {
+            Some(total) => total,
+            None => return error(line, format!("'{}' is out of range", expr)),
+          };
+          negative = false;
+          after_term = true;
+        }
+      }
+    }
+
+    if !after_term {
+      return error(line, format!("missing operand in '{}'", expr));
+    }
+    Ok(total)
+  }
+
+  // The value of a number or label, negated if `negative`. Numbers are
+  // parsed with their sign so that i64::MIN can be written.
+  fn term(&self, line: usize, term: &str, negative: bool) -> Result<i64, AssembleError> {
+    if term.starts_with(|c: char| c.is_ascii_digit()) {
+      let signed = if negative {
+        format!("-{}", term)
+      } else {
+        term.to_string()
+      };
+      return match signed.parse::<i64>() {
+        Ok(value) => Ok(value),
+        Err(_) => error(line, format!("'{}' is out of range", signed)),
+      };
+    }
+
+    match self.labels.get(term) {
+      Some(&address) if negative => Ok(-address),
+      Some(&address) => Ok(address),
+      None => error(line, format!("undefined label '{}'", term)),
+    }
+  }
+}
+
+fn parse_statement(line: usize, keyword: &str, rest: &str) -> Result<Statement, AssembleError> {
+  match keyword {
+    "data" => {
+      let values = split_list(rest);
+      if values.is_empty() {
+        return error(line, String::from("data needs at least one value"));
+      }
+      Ok(Statement::Data(values))
+    }
+    "space" => match rest.parse() {
+      Ok(count) => Ok(Statement::Space(count)),
+      Err(_) => error(line, format!("invalid space size '{}'", rest)),
+    },
+    _ => {
+      let opcode = match Opcode::from_mnemonic(keyword) {
+        Some(opcode) => opcode,
+        None => return error(line, format!("unknown mnemonic '{}'", keyword)),
+      };
+
+      // `add a, b -> c` and `add a, b, c` are both accepted
+      let mut operands = Vec::new();
+      let (inputs, output) = match rest.find("->") {
+        Some(arrow) => (&rest[..arrow], Some(rest[arrow + 2..].trim())),
+        None => (rest, None),
+      };
+      for operand in split_list(inputs).iter().map(String::as_str).chain(output) {
+        operands.push(parse_operand(line, operand)?);
+      }
+
+      if operands.len() != opcode.arity() {
+        return error(
+          line,
+          format!(
+            "'{}' takes {} operands but {} were given",
+            keyword,
+            opcode.arity(),
+            operands.len()
+          ),
+        );
+      }
+
+      if let Some(index) = opcode.write_parameter() {
+        if let Operand::Immediate(_) = operands[index] {
+          return error(
+            line,
+            format!("'{}' can't write to an immediate operand", keyword),
+          );
+        }
+      }
+
+      Ok(Statement::Instruction(opcode, operands))
+    }
+  }
+}
+
+fn parse_operand(line: usize, operand: &str) -> Result<Operand, AssembleError> {
+  let operand = operand.trim();
+
+  if let Some(value) = operand.strip_prefix('#') {
+    return Ok(Operand::Immediate(value.to_string()));
+  }
+
+  if operand.starts_with('[') && operand.ends_with(']') {
+    let inner = operand[1..operand.len() - 1].trim();
+
+    for base in ["rb", "fp"].iter() {
+      if let Some(offset) = inner.strip_prefix(base) {
+        let offset = offset.trim();
+        if offset.is_empty() {
+          return Ok(Operand::Relative(String::from("0")));
+        }
+        if offset.starts_with('+') || offset.starts_with('-') {
+          return Ok(Operand::Relative(offset.to_string()));
+        }
+      }
+    }
+
+    return Ok(Operand::Position(inner.to_string()));
+  }
+
+  error(line, format!("invalid operand '{}'", operand))
+}
+
+fn strip_comment(line: &str) -> &str {
+  match line.find(';') {
+    Some(start) => &line[..start],
+    None => line,
+  }
+}
+
+fn split_list(text: &str) -> Vec<String> {
+  let text = text.trim();
+  if text.is_empty() {
+    return Vec::new();
+  }
+
+  text
+    .split(',')
+    .map(|item| item.trim().to_string())
+    .collect()
+}
+
+// Splits an expression into `+`, `-` and the terms between them.
+fn tokenize(expr: &str) -> Vec<&str> {
+  let mut tokens = Vec::new();
+  let mut start = None;
+
+  for (i, c) in expr.char_indices() {
+    if c.is_whitespace() || c == '+' || c == '-' {
+      if let Some(start) = start.take() {
+        tokens.push(&expr[start..i]);
+      }
+      if !c.is_whitespace() {
+        tokens.push(&expr[i..i + 1]);
+      }
+    } else if start.is_none() {
+      start = Some(i);
+    }
+  }
+  if let Some(start) = start {
+    tokens.push(&expr[start..]);
+  }
+
+  tokens
+}
+
+fn is_identifier(name: &str) -> bool {
+  let mut chars = name.chars();
+
+  match chars.next() {
+    Some(c) if c.is_ascii_alphabetic() || c == '_' || c == '.' => {
+      chars.all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '.' || c == '@')
+    }
+    _ => false,
+  }
+}
+
+// Replaces whole-word occurrences of the macro parameters with the given
+// arguments, and every `@` with the expansion's unique number.
+fn substitute(line: &str, params: &[String], args: &[String], unique: &str) -> String {
+  let mut result = String::new();
+  let mut word = String::new();
+
+  let flush = |word: &mut String, result: &mut String| {
+    match params.iter().position(|param| param == word) {
+      Some(index) => result.push_str(&args[index]),
+      None => result.push_str(word),
+    }
+    word.clear();
+  };
+
+  for c in strip_comment(line).chars() {
+    if c.is_ascii_alphanumeric() || c == '_' || c == '.' {
+      word.push(c);
+    } else {
+      flush(&mut word, &mut result);
+      if c == '@' {
+        result.push_str(unique);
+      } else {
+        result.push(c);
+      }
+    }
+  }
+  flush(&mut word, &mut result);
+
+  result
+}
@@ -0,0 +1,16 @@
+use std::env;
+use std::fs;
+use std::process;
+
+fn main() {
+  let filename = env::args().nth(1).expect("Missing input file argument");
+  let source = fs::read_to_string(&filename).expect("Failed to read input file");
+
+  match intcode::assemble(&source) {
+    Ok(words) => println!("{}", intcode::format_program(&words)),
+    Err(err) => {
+      eprintln!("{}: {}", filename, err);
+      process::exit(1);
+    }
+  }
+}
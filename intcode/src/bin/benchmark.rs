@@ -0,0 +1,101 @@
+use intcode::{Computer, Engine, Interruption};
+use std::env;
+use std::fs;
+use std::time::{Duration, Instant};
+
+const ROUNDS: usize = 5;
+
+type Workload = fn(&str, Engine) -> Vec<i64>;
+
+// Runs BOOST in sensor boost mode, returning its outputs.
+fn boost(program: &str, engine: Engine) -> Vec<i64> {
+  let mut computer = Computer::new();
+  computer.set_engine(engine);
+  computer.load(program);
+  computer.input(2);
+
+  let mut outputs = Vec::new();
+  loop {
+    match computer.run() {
+      Interruption::Output => outputs.push(computer.output().unwrap()),
+      Interruption::Halt => return outputs,
+      interruption => panic!("unexpected interruption: {:?}", interruption),
+    }
+  }
+}
+
+// Plays the breakout game to the end by keeping the paddle under the ball,
+// returning the final score.
+fn breakout(program: &str, engine: Engine) -> Vec<i64> {
+  let mut computer = Computer::new();
+  computer.set_engine(engine);
+  computer.load(program);
+  computer.write(0, 2).expect("Failed to insert quarters");
+
+  let (mut ball, mut paddle, mut score) = (0, 0, 0);
+  loop {
+    match computer.run() {
+      Interruption::Output => {
+        if computer.pending_output().len() < 3 {
+          continue;
+        }
+
+        let x = computer.output().unwrap();
+        let y = computer.output().unwrap();
+        let tile = computer.output().unwrap();
+        match (x, y, tile) {
+          (-1, 0, value) => score = value,
+          (x, _, 3) => paddle = x,
+          (x, _, 4) => ball = x,
+          _ => (),
+        }
+      }
+      Interruption::Input => computer.input((ball - paddle).signum()),
+      Interruption::Halt => return vec![score],
+      interruption => panic!("unexpected interruption: {:?}", interruption),
+    }
+  }
+}
+
+fn measure(workload: Workload, program: &str, engine: Engine) -> (Duration, Vec<i64>) {
+  let mut best = Duration::from_secs(u64::MAX);
+  let mut result = Vec::new();
+
+  for _ in 0..ROUNDS {
+    let start = Instant::now();
+    result = workload(program, engine);
+    best = best.min(start.elapsed());
+  }
+
+  (best, result)
+}
+
+fn main() {
+  let args: Vec<String> = env::args().collect();
+  if args.len() != 3 {
+    eprintln!("usage: {} <day09 input> <day13 input>", args[0]);
+    std::process::exit(1);
+  }
+
+  let workloads: [(&str, Workload, &String); 2] =
+    [("boost", boost, &args[1]), ("breakout", breakout, &args[2])];
+
+  for (name, workload, filename) in workloads.iter() {
+    let program = fs::read_to_string(filename).expect("Failed to read input file");
+    println!("{} ({})", name, filename);
+
+    let (interpreted, expected) = measure(*workload, &program, Engine::Interpreter);
+    let (cached, result) = measure(*workload, &program, Engine::Cached);
+    assert_eq!(result, expected, "engines disagree on {}", name);
+
+    println!(
+      "  interpreter {:>10.3} ms",
+      interpreted.as_secs_f64() * 1000.0
+    );
+    println!(
+      "  cached      {:>10.3} ms  {:.2}x",
+      cached.as_secs_f64() * 1000.0,
+      interpreted.as_secs_f64() / cached.as_secs_f64()
+    );
+  }
+}
@@ -0,0 +1,48 @@
+use intcode::{Computer, Coverage, Interruption};
+use std::env;
+use std::fs;
+
+// Runs a program once per input set, separated by `--`, and prints its
+// disassembly annotated with the combined coverage of every run:
+//
+//   coverage <program> [input...] [-- input...]
+fn main() {
+  let args: Vec<String> = env::args().skip(1).collect();
+  if args.is_empty() {
+    eprintln!("usage: coverage <program> [input...] [-- input...]");
+    std::process::exit(1);
+  }
+
+  let raw = fs::read_to_string(&args[0]).expect("Failed to read input file");
+  let mut coverage = Coverage::new();
+
+  for inputs in args[1..].split(|arg| arg == "--") {
+    let mut computer = Computer::new();
+    computer.load(&raw);
+    for value in inputs.iter() {
+      computer.input(value.parse().expect("Invalid input value"));
+    }
+
+    computer.start_coverage(coverage);
+    loop {
+      match computer.run() {
+        Interruption::Output => {
+          computer.output();
+        }
+        Interruption::Input => {
+          eprintln!("program is waiting for more input, stopping this run");
+          break;
+        }
+        Interruption::Halt => break,
+        Interruption::Fault(fault) => {
+          eprintln!("computer faulted: {}", fault);
+          break;
+        }
+        Interruption::BudgetExhausted => unreachable!(),
+      }
+    }
+    coverage = computer.stop_coverage().expect("Coverage wasn't on");
+  }
+
+  print!("{}", coverage.annotate(&intcode::parse(&raw)));
+}
@@ -0,0 +1,37 @@
+use intcode::{Computer, Debugger};
+use std::env;
+use std::io;
+use std::io::Write;
+
+fn main() {
+  let filename = env::args().nth(1).expect("Missing input file argument");
+
+  let mut computer = Computer::new();
+  computer.flash(&filename);
+
+  let mut debugger = Debugger::new(computer);
+  println!("{}", debugger.execute("disasm").unwrap_or_default());
+
+  loop {
+    print!("(intcode) ");
+    io::stdout().flush().expect("Failed to flush stdout");
+
+    let mut line = String::new();
+    if io::stdin()
+      .read_line(&mut line)
+      .expect("Failed to read command")
+      == 0
+    {
+      break;
+    }
+
+    match debugger.execute(&line) {
+      Some(text) => {
+        if !text.is_empty() {
+          println!("{}", text);
+        }
+      }
+      None => break,
+    }
+  }
+}
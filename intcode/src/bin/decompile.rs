@@ -0,0 +1,9 @@
+use std::env;
+use std::fs;
+
+fn main() {
+  let filename = env::args().nth(1).expect("Missing input file argument");
+  let raw = fs::read_to_string(filename).expect("Failed to read input file");
+
+  print!("{}", intcode::decompile(&intcode::parse(&raw)));
+}
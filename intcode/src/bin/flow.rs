@@ -0,0 +1,18 @@
+use std::env;
+use std::fs;
+
+fn main() {
+  let filename = env::args().nth(1).expect("Missing input file argument");
+  let raw = fs::read_to_string(filename).expect("Failed to read input file");
+
+  let graph = intcode::analyze(&intcode::parse(&raw));
+
+  for modification in graph.modifications.iter() {
+    eprintln!(
+      "self-modifying: {} writes {}, part of the instruction at {}",
+      modification.writer, modification.address, modification.instruction
+    );
+  }
+
+  print!("{}", graph.to_dot());
+}
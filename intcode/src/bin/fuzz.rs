@@ -0,0 +1,32 @@
+use intcode::Fuzzer;
+use std::env;
+
+// Runs random programs on every implementation and prints the minimized
+// ones they disagree on:
+//
+//   fuzz [iterations] [seed]
+fn main() {
+  let args: Vec<String> = env::args().skip(1).collect();
+  let iterations = match args.first() {
+    Some(arg) => arg.parse().expect("Invalid iteration count"),
+    None => 1000,
+  };
+  let seed = match args.get(1) {
+    Some(arg) => arg.parse().expect("Invalid seed"),
+    None => 1,
+  };
+
+  let discrepancies = Fuzzer::new(seed).fuzz(iterations);
+  for discrepancy in discrepancies.iter() {
+    println!("{}\n", discrepancy);
+  }
+
+  println!(
+    "{} programs, {} discrepancies",
+    iterations,
+    discrepancies.len()
+  );
+  if !discrepancies.is_empty() {
+    std::process::exit(1);
+  }
+}
@@ -0,0 +1,50 @@
+use intcode::{Computer, Interruption};
+use std::env;
+
+// Runs a program with profiling on, feeding it the given inputs, and prints
+// where it spent its time:
+//
+//   profile [--csv] <program> [input...]
+fn main() {
+  let mut args: Vec<String> = env::args().skip(1).collect();
+  let csv = args.first().map(String::as_str) == Some("--csv");
+  if csv {
+    args.remove(0);
+  }
+  if args.is_empty() {
+    eprintln!("usage: profile [--csv] <program> [input...]");
+    std::process::exit(1);
+  }
+
+  let mut computer = Computer::new();
+  computer.flash(&args[0]);
+  for value in args[1..].iter() {
+    computer.input(value.parse().expect("Invalid input value"));
+  }
+
+  computer.start_profiling();
+  loop {
+    match computer.run() {
+      Interruption::Output => {
+        computer.output();
+      }
+      Interruption::Input => {
+        eprintln!("program is waiting for more input, stopping");
+        break;
+      }
+      Interruption::Halt => break,
+      Interruption::Fault(fault) => {
+        eprintln!("computer faulted: {}", fault);
+        break;
+      }
+      Interruption::BudgetExhausted => unreachable!(),
+    }
+  }
+
+  let profile = computer.stop_profiling().expect("Profiling wasn't on");
+  if csv {
+    print!("{}", profile.to_csv());
+  } else {
+    print!("{}", profile.report());
+  }
+}
@@ -0,0 +1,31 @@
+use intcode::{Computer, Transcript};
+use std::env;
+use std::process;
+
+// Replays a transcript recorded with INTCODE_RECORD against a program,
+// reporting the first output that doesn't match:
+//
+//   replay <program> <transcript>
+fn main() {
+  let args: Vec<String> = env::args().skip(1).collect();
+  if args.len() != 2 {
+    eprintln!("usage: replay <program> <transcript>");
+    process::exit(1);
+  }
+
+  let transcript = Transcript::open(&args[1]).unwrap_or_else(|err| {
+    eprintln!("{}: {}", args[1], err);
+    process::exit(1);
+  });
+
+  let mut computer = Computer::new();
+  computer.flash(&args[0]);
+
+  match intcode::replay(&mut computer, &transcript) {
+    Ok(count) => println!("replayed {} entries, no divergence", count),
+    Err(divergence) => {
+      println!("{}", divergence);
+      process::exit(2);
+    }
+  }
+}
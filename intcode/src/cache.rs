@@ -0,0 +1,82 @@
+use crate::instruction::{Instruction, Opcode, Parameter};
+
+// Only instructions below this address are cached; anything past it is left
+// to the interpreter rather than growing the cache without bound.
+const CACHE_LIMIT: u64 = 1 << 20;
+
+// An instruction decoded once and kept around for the next time execution
+// reaches its address. Parameters are stored inline so executing it doesn't
+// chase any pointers.
+#[derive(Debug, Copy, Clone)]
+pub struct Decoded {
+  pub opcode: Opcode,
+  pub params: [Parameter; 3],
+}
+
+impl Decoded {
+  fn from_instruction(instruction: &Instruction) -> Decoded {
+    let mut params = [Parameter::Immediate(0); 3];
+    params[..instruction.params.len()].copy_from_slice(&instruction.params);
+
+    Decoded {
+      opcode: instruction.opcode,
+      params,
+    }
+  }
+}
+
+// Decoded instructions by address. Any write to memory must go through
+// `invalidate`, so instructions the program rewrites get decoded again.
+#[derive(Debug, Clone, Default)]
+pub struct DecodeCache {
+  entries: Vec<Option<Decoded>>,
+}
+
+impl DecodeCache {
+  pub fn new() -> DecodeCache {
+    DecodeCache {
+      entries: Vec::new(),
+    }
+  }
+
+  pub fn get(&self, addr: u64) -> Option<Decoded> {
+    match self.entries.get(addr as usize) {
+      Some(&entry) => entry,
+      None => None,
+    }
+  }
+
+  // Decodes the instruction starting at `addr` from `words` and caches it.
+  // Returns `None` for anything that doesn't decode cleanly, so the caller
+  // can let the interpreter deal with it (and fault, if it must).
+  pub fn decode(&mut self, addr: u64, words: &[i64]) -> Option<Decoded> {
+    if addr >= CACHE_LIMIT {
+      return None;
+    }
+
+    let decoded = Decoded::from_instruction(&Instruction::decode(words)?);
+
+    let index = addr as usize;
+    if index >= self.entries.len() {
+      self.entries.resize(index + 1, None);
+    }
+    self.entries[index] = Some(decoded);
+
+    Some(decoded)
+  }
+
+  // Forgets every cached instruction that covers `addr`. Instructions are at
+  // most 4 words long, so only the ones starting up to 3 words before it can.
+  pub fn invalidate(&mut self, addr: u64) {
+    let start = addr.saturating_sub(3) as usize;
+    let end = (addr as usize + 1).min(self.entries.len());
+
+    for index in start..end {
+      self.entries[index] = None;
+    }
+  }
+
+  pub fn clear(&mut self) {
+    self.entries.clear();
+  }
+}
@@ -0,0 +1,1041 @@
+use crate::cache::DecodeCache;
+use crate::coverage::Coverage;
+use crate::fault::Fault;
+use crate::instruction::{Instruction, Opcode, Parameter};
+use crate::io::{InputSource, OutputSink};
+use crate::memory::Memory;
+use crate::plugin::{Access, Effect, Plugin, Registry};
+use crate::profiler::Profile;
+use crate::snapshot::{Snapshot, SnapshotError};
+use crate::tracer::{Operation, TraceEvent, Tracer};
+use crate::transcript::{Entry, Recorder};
+use crate::word::{narrow, Word};
+use std::collections::VecDeque;
+use std::fmt;
+use std::fs;
+
+// Programs may address up to this many words; the memory itself only grows
+// as far as the program actually writes.
+pub(crate) const MEMORY_LIMIT: u64 = 1 << 30;
+
+#[derive(Debug)]
+pub enum State {
+  Booting,
+  Ready,
+  Running,
+  Interrupted,
+  Halted,
+  Faulted,
+}
+
+// How the computer executes instructions. The interpreter decodes every
+// instruction each time it runs it; the cached engine decodes each address
+// once and reuses that until the program writes over it.
+#[derive(Debug, PartialEq, Eq, Copy, Clone)]
+pub enum Engine {
+  Interpreter,
+  Cached,
+}
+
+// What add and multiply do when the result doesn't fit in a word: wrap
+// around, or fault.
+#[derive(Debug, PartialEq, Eq, Copy, Clone)]
+pub enum Arithmetic {
+  Wrapping,
+  Checked,
+}
+
+impl Copy for State {}
+
+impl Clone for State {
+  fn clone(&self) -> State {
+    *self
+  }
+}
+
+// A computer whose memory holds words of type `W`, i64 unless asked
+// otherwise (`Computer::<BigInt>::default()`).
+pub struct Computer<W: Word = i64> {
+  state: State,
+  memory: Memory<W>,
+  input_buffer: VecDeque<W>,
+  output_buffer: VecDeque<W>,
+  input_source: Option<Box<dyn InputSource>>,
+  output_sink: Option<Box<dyn OutputSink>>,
+  pc: u64,
+  fp: i64,
+  executed: u64,
+  arithmetic: Arithmetic,
+  engine: Engine,
+  cache: DecodeCache,
+  profile: Option<Profile>,
+  coverage: Option<Coverage>,
+  tracer: Option<Box<dyn Tracer>>,
+  recorder: Option<Recorder>,
+  last_write: Option<(u64, i64)>,
+  plugins: Registry<W>,
+}
+
+#[derive(Debug)]
+pub enum Interruption {
+  Halt,
+  Output,
+  Input,
+  Fault(Fault),
+  // returned by `run_for` when it ran out of instructions to execute
+  BudgetExhausted,
+}
+
+impl<W: Word> fmt::Debug for Computer<W> {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    write!(
+      f,
+      "Computer {{ state: {:?}, pc: {}, fp: {}, executed: {}, input: {:?}, output: {:?} }}",
+      self.state, self.pc, self.fp, self.executed, self.input_buffer, self.output_buffer
+    )
+  }
+}
+
+// Clones don't inherit the I/O connections, tracer, recorder, profile or
+// coverage. They do keep the plug-in opcodes.
+impl<W: Word> Clone for Computer<W> {
+  fn clone(&self) -> Computer<W> {
+    Computer {
+      state: self.state,
+      memory: self.memory.clone(),
+      input_buffer: self.input_buffer.clone(),
+      output_buffer: self.output_buffer.clone(),
+      input_source: None,
+      output_sink: None,
+      pc: self.pc,
+      fp: self.fp,
+      executed: self.executed,
+      arithmetic: self.arithmetic,
+      engine: self.engine,
+      cache: DecodeCache::new(),
+      profile: None,
+      coverage: None,
+      tracer: None,
+      recorder: None,
+      last_write: None,
+      plugins: self.plugins.clone(),
+    }
+  }
+}
+
+pub fn parse(program: &str) -> Vec<i64> {
+  parse_words(program)
+}
+
+fn parse_words<W: Word>(program: &str) -> Vec<W> {
+  program
+    .trim()
+    .split(',')
+    .map(|val| {
+      val
+        .trim()
+        .parse()
+        .unwrap_or_else(|_| panic!("Failed to parse value from input"))
+    })
+    .collect()
+}
+
+impl<W: Word> Default for Computer<W> {
+  fn default() -> Computer<W> {
+    Computer {
+      state: State::Booting,
+      memory: Memory::new(),
+      input_buffer: VecDeque::new(),
+      output_buffer: VecDeque::new(),
+      input_source: None,
+      output_sink: None,
+      pc: 0,
+      fp: 0,
+      executed: 0,
+      arithmetic: Arithmetic::Wrapping,
+      engine: Engine::Interpreter,
+      cache: DecodeCache::new(),
+      profile: None,
+      coverage: None,
+      tracer: None,
+      recorder: None,
+      last_write: None,
+      plugins: Registry::new(),
+    }
+  }
+}
+
+impl Computer {
+  pub fn new() -> Computer {
+    Computer::default()
+  }
+
+  pub fn snapshot(&self) -> Snapshot {
+    Snapshot {
+      state: self.state,
+      pc: self.pc,
+      fp: self.fp,
+      executed: self.executed,
+      arithmetic: self.arithmetic,
+      engine: self.engine,
+      memory: self.memory.clone(),
+      input: self.input_buffer.clone(),
+      output: self.output_buffer.clone(),
+    }
+  }
+
+  pub fn from_snapshot(snapshot: Snapshot) -> Computer {
+    Computer {
+      state: snapshot.state,
+      memory: snapshot.memory,
+      input_buffer: snapshot.input,
+      output_buffer: snapshot.output,
+      pc: snapshot.pc,
+      fp: snapshot.fp,
+      executed: snapshot.executed,
+      arithmetic: snapshot.arithmetic,
+      engine: snapshot.engine,
+      ..Computer::default()
+    }
+  }
+
+  // Checkpoints the machine to disk, so a long run can be resumed later with
+  // `Computer::restore`.
+  pub fn save(&self, filename: &str) -> Result<(), SnapshotError> {
+    self.snapshot().save(filename)
+  }
+
+  pub fn restore(filename: &str) -> Result<Computer, SnapshotError> {
+    Ok(Computer::from_snapshot(Snapshot::open(filename)?))
+  }
+}
+
+impl<W: Word> Computer<W> {
+  pub fn flash(&mut self, filename: &str) {
+    let raw = fs::read_to_string(filename).expect("Failed to read input file");
+    self.load(&raw);
+  }
+
+  pub fn load(&mut self, program: &str) {
+    for (i, n) in parse_words(program).into_iter().enumerate() {
+      self.memory.write(i as u64, n);
+    }
+    self.cache.clear();
+
+    self.state = State::Ready;
+  }
+
+  pub fn dump(&self) {
+    for addr in 0..128 {
+      print!("{} ", self.memory.read(addr));
+    }
+    println!();
+  }
+
+  pub fn get_state(&self) -> State {
+    self.state
+  }
+
+  pub fn input(&mut self, value: W) {
+    if let Some(recorder) = self.recorder.as_mut() {
+      recorder.record(Entry::Input {
+        value: narrow(&value),
+        executed: self.executed,
+      });
+    }
+
+    self.input_buffer.push_back(value);
+  }
+
+  pub fn output(&mut self) -> Option<W> {
+    let value = self.output_buffer.pop_front();
+
+    if let (Some(recorder), Some(value)) = (self.recorder.as_mut(), value.as_ref()) {
+      recorder.record(Entry::Output {
+        value: narrow(value),
+        executed: self.executed,
+      });
+    }
+
+    value
+  }
+
+  // Connects a source the machine pulls input from whenever its input buffer
+  // is empty, instead of interrupting.
+  pub fn set_input_source(&mut self, source: Box<dyn InputSource>) {
+    self.input_source = Some(source);
+  }
+
+  pub fn take_input_source(&mut self) -> Option<Box<dyn InputSource>> {
+    self.input_source.take()
+  }
+
+  // Connects a sink the machine streams its output to, instead of buffering
+  // it and interrupting.
+  pub fn set_output_sink(&mut self, sink: Box<dyn OutputSink>) {
+    self.output_sink = Some(sink);
+  }
+
+  pub fn take_output_sink(&mut self) -> Option<Box<dyn OutputSink>> {
+    self.output_sink.take()
+  }
+
+  // Makes sure there's something in the input buffer, pulling from the input
+  // source if needed. Returns false if there's still no input. Input that
+  // doesn't fit in a word wraps around, or faults in checked mode.
+  fn fill_input(&mut self) -> Result<bool, Fault> {
+    if self.input_buffer.is_empty() {
+      if let Some(value) = self
+        .input_source
+        .as_mut()
+        .and_then(|source| source.next_input())
+      {
+        let word = match self.arithmetic {
+          Arithmetic::Wrapping => W::from_i64(value),
+          Arithmetic::Checked => W::checked_from_i64(value).ok_or_else(|| self.overflow())?,
+        };
+        self.input(word);
+      }
+    }
+
+    Ok(!self.input_buffer.is_empty())
+  }
+
+  // Hands an output to the sink, or buffers it. Returns whether the machine
+  // should interrupt for it.
+  fn emit_output(&mut self, value: W) -> bool {
+    match self.output_sink.as_mut() {
+      Some(sink) => {
+        let value = narrow(&value);
+        sink.send_output(value);
+        if let Some(recorder) = self.recorder.as_mut() {
+          recorder.record(Entry::Output {
+            value,
+            executed: self.executed,
+          });
+        }
+        false
+      }
+      None => {
+        self.output_buffer.push_back(value);
+        true
+      }
+    }
+  }
+
+  pub fn pc(&self) -> u64 {
+    self.pc
+  }
+
+  pub fn fp(&self) -> i64 {
+    self.fp
+  }
+
+  // Number of instructions executed since the machine booted.
+  pub fn executed(&self) -> u64 {
+    self.executed
+  }
+
+  pub fn pending_input(&self) -> &VecDeque<W> {
+    &self.input_buffer
+  }
+
+  pub fn pending_output(&self) -> &VecDeque<W> {
+    &self.output_buffer
+  }
+
+  pub fn set_tracer(&mut self, tracer: Box<dyn Tracer>) {
+    self.tracer = Some(tracer);
+  }
+
+  pub fn take_tracer(&mut self) -> Option<Box<dyn Tracer>> {
+    self.tracer.take()
+  }
+
+  // Logs every value passed to `input` and returned by `output` from now on.
+  pub fn set_recorder(&mut self, recorder: Recorder) {
+    self.recorder = Some(recorder);
+  }
+
+  pub fn take_recorder(&mut self) -> Option<Recorder> {
+    self.recorder.take()
+  }
+
+  // Starts counting executed instructions per address and opcode, along
+  // with taken backward jumps. Like tracing, this runs on the interpreter.
+  pub fn start_profiling(&mut self) {
+    self.profile = Some(Profile::new());
+  }
+
+  pub fn profile(&self) -> Option<&Profile> {
+    self.profile.as_ref()
+  }
+
+  pub fn stop_profiling(&mut self) -> Option<Profile> {
+    self.profile.take()
+  }
+
+  // Starts recording which addresses get executed, read and written. Pass the
+  // coverage of earlier runs to keep adding to it. Like tracing, this runs on
+  // the interpreter.
+  pub fn start_coverage(&mut self, coverage: Coverage) {
+    self.coverage = Some(coverage);
+  }
+
+  pub fn coverage(&self) -> Option<&Coverage> {
+    self.coverage.as_ref()
+  }
+
+  pub fn stop_coverage(&mut self) -> Option<Coverage> {
+    self.coverage.take()
+  }
+
+  pub fn memory(&self) -> &Memory<W> {
+    &self.memory
+  }
+
+  pub fn read(&self, index: u64) -> W {
+    self.memory.read(index)
+  }
+
+  // Writes a word on the host's behalf, faulting like the program would if
+  // the address is beyond what programs may address. Recorded in the
+  // transcript, so replays patch the program the same way.
+  pub fn write(&mut self, index: u64, value: W) -> Result<(), Fault> {
+    self.memory.write(self.check_address(index)?, value.clone());
+    self.cache.invalidate(index);
+
+    if let Some(recorder) = self.recorder.as_mut() {
+      recorder.record(Entry::Write {
+        address: index,
+        value: narrow(&value),
+        executed: self.executed,
+      });
+    }
+    Ok(())
+  }
+
+  pub fn plugins(&self) -> &Registry<W> {
+    &self.plugins
+  }
+
+  // Extra opcodes the computer runs instead of faulting on them.
+  pub fn plugins_mut(&mut self) -> &mut Registry<W> {
+    &mut self.plugins
+  }
+
+  pub fn arithmetic(&self) -> Arithmetic {
+    self.arithmetic
+  }
+
+  // In checked mode, an add or multiply whose result doesn't fit in a word
+  // raises `Fault::Overflow` instead of wrapping around.
+  pub fn set_arithmetic(&mut self, arithmetic: Arithmetic) {
+    self.arithmetic = arithmetic;
+  }
+
+  pub fn engine(&self) -> Engine {
+    self.engine
+  }
+
+  pub fn set_engine(&mut self, engine: Engine) {
+    self.engine = engine;
+    self.cache.clear();
+  }
+
+  fn instruction(&self) -> i64 {
+    narrow(&self.memory.read(self.pc))
+  }
+
+  fn overflow(&self) -> Fault {
+    Fault::Overflow {
+      pc: self.pc,
+      fp: self.fp,
+      instruction: self.instruction(),
+    }
+  }
+
+  fn add(&self, a: &W, b: &W) -> Result<W, Fault> {
+    match self.arithmetic {
+      Arithmetic::Wrapping => Ok(a.wrapping_add(b)),
+      Arithmetic::Checked => a.checked_add(b).ok_or_else(|| self.overflow()),
+    }
+  }
+
+  fn multiply(&self, a: &W, b: &W) -> Result<W, Fault> {
+    match self.arithmetic {
+      Arithmetic::Wrapping => Ok(a.wrapping_mul(b)),
+      Arithmetic::Checked => a.checked_mul(b).ok_or_else(|| self.overflow()),
+    }
+  }
+
+  fn adjust_relative_base(&mut self, value: &W) -> Result<(), Fault> {
+    self.fp = value
+      .to_i64()
+      .and_then(|value| self.fp.checked_add(value))
+      .ok_or_else(|| self.overflow())?;
+    Ok(())
+  }
+
+  // Words too big to be an address are clamped, so they fault as negative or
+  // out of range like any other bad address.
+  fn relative_address(&self, offset: &W) -> Result<u64, Fault> {
+    self.to_address(self.fp.saturating_add(narrow(offset)))
+  }
+
+  fn to_address(&self, value: i64) -> Result<u64, Fault> {
+    if value < 0 {
+      return Err(Fault::NegativeAddress {
+        pc: self.pc,
+        fp: self.fp,
+        instruction: self.instruction(),
+        address: value,
+      });
+    }
+
+    Ok(value as u64)
+  }
+
+  fn check_address(&self, addr: u64) -> Result<u64, Fault> {
+    if addr >= MEMORY_LIMIT {
+      return Err(Fault::AddressOutOfRange {
+        pc: self.pc,
+        fp: self.fp,
+        instruction: self.instruction(),
+        address: addr,
+      });
+    }
+
+    Ok(addr)
+  }
+
+  fn fetch(&self, addr: u64) -> Result<W, Fault> {
+    Ok(self.memory.read(self.check_address(addr)?))
+  }
+
+  fn store(&mut self, addr: u64, value: W) -> Result<(), Fault> {
+    let addr = self.check_address(addr)?;
+    self.last_write = Some((addr, narrow(&value)));
+    self.memory.write(addr, value);
+    self.cache.invalidate(addr);
+    Ok(())
+  }
+
+  fn get_param_mode(&self, index: u64) -> i64 {
+    (self.instruction() / i64::pow(10, (index + 2) as u32)) % 10
+  }
+
+  fn get_read_addr(&self, index: u64) -> Result<u64, Fault> {
+    let param_mode = self.get_param_mode(index);
+    let param_addr = self.pc + index + 1;
+    match param_mode {
+      0 => self.to_address(narrow(&self.fetch(param_addr)?)),
+      1 => Ok(param_addr),
+      2 => self.relative_address(&self.fetch(param_addr)?),
+      mode => Err(Fault::InvalidParameterMode {
+        pc: self.pc,
+        fp: self.fp,
+        instruction: self.instruction(),
+        mode,
+      }),
+    }
+  }
+
+  fn get_write_addr(&self, index: u64) -> Result<u64, Fault> {
+    let param_mode = self.get_param_mode(index);
+    let param_addr = self.pc + index + 1;
+    match param_mode {
+      0 => self.to_address(narrow(&self.fetch(param_addr)?)),
+      1 => Err(Fault::WriteInImmediateMode {
+        pc: self.pc,
+        fp: self.fp,
+        instruction: self.instruction(),
+      }),
+      2 => self.relative_address(&self.fetch(param_addr)?),
+      mode => Err(Fault::InvalidParameterMode {
+        pc: self.pc,
+        fp: self.fp,
+        instruction: self.instruction(),
+        mode,
+      }),
+    }
+  }
+
+  pub fn run(&mut self) -> Interruption {
+    self.state = State::Running;
+
+    match self.execute() {
+      Ok(interruption) => interruption,
+      Err(fault) => {
+        self.state = State::Faulted;
+        Interruption::Fault(fault)
+      }
+    }
+  }
+
+  // Like `run`, but gives up after executing `max_instructions`, so a runaway
+  // program can't hang the caller. Calling it again picks up where it left
+  // off.
+  pub fn run_for(&mut self, max_instructions: u64) -> Interruption {
+    self.state = State::Running;
+
+    for _ in 0..max_instructions {
+      match self.execute_instruction() {
+        Ok(Some(interruption)) => return interruption,
+        Ok(None) => (),
+        Err(fault) => {
+          self.state = State::Faulted;
+          return Interruption::Fault(fault);
+        }
+      }
+    }
+
+    self.state = State::Interrupted;
+    Interruption::BudgetExhausted
+  }
+
+  // Executes a single instruction, returning the interruption it raised, if
+  // any.
+  pub fn step(&mut self) -> Option<Interruption> {
+    self.state = State::Running;
+
+    match self.execute_instruction() {
+      Ok(Some(interruption)) => Some(interruption),
+      Ok(None) => {
+        self.state = State::Interrupted;
+        None
+      }
+      Err(fault) => {
+        self.state = State::Faulted;
+        Some(Interruption::Fault(fault))
+      }
+    }
+  }
+
+  fn execute(&mut self) -> Result<Interruption, Fault> {
+    loop {
+      if let Some(interruption) = self.execute_instruction()? {
+        return Ok(interruption);
+      }
+    }
+  }
+
+  fn execute_instruction(&mut self) -> Result<Option<Interruption>, Fault> {
+    let result = self.trace_instruction();
+
+    // an input instruction waiting for input didn't actually execute
+    match result {
+      Ok(Some(Interruption::Input)) | Err(_) => (),
+      Ok(_) => self.executed += 1,
+    }
+
+    result
+  }
+
+  fn trace_instruction(&mut self) -> Result<Option<Interruption>, Fault> {
+    if self.tracer.is_none() && self.profile.is_none() && self.coverage.is_none() {
+      return match self.engine {
+        Engine::Interpreter => self.interpret(),
+        Engine::Cached => self.execute_cached(),
+      };
+    }
+
+    let pc = self.pc;
+    let fp = self.fp;
+    let (operation, addresses, values) = self.resolve_operands();
+
+    self.last_write = None;
+    let result = self.interpret();
+
+    // an input instruction waiting for input didn't actually execute
+    if let Ok(Some(Interruption::Input)) = result {
+      return result;
+    }
+
+    let event = TraceEvent {
+      pc,
+      fp,
+      operation,
+      addresses,
+      values,
+      write: self.last_write,
+      fault: result.as_ref().err().copied(),
+    };
+
+    if result.is_ok() {
+      if let Some(profile) = self.profile.as_mut() {
+        profile.record(&event);
+      }
+      if let Some(coverage) = self.coverage.as_mut() {
+        coverage.record(&event);
+      }
+    }
+    if let Some(tracer) = self.tracer.as_mut() {
+      tracer.trace(&event);
+    }
+
+    result
+  }
+
+  // Decodes what's at pc along with the address and current value of each
+  // of its operands, for tracing.
+  #[allow(clippy::type_complexity)]
+  fn resolve_operands(&self) -> (Operation, Vec<Option<u64>>, Vec<i64>) {
+    let operation = self.decode_operation();
+
+    let addresses: Vec<Option<u64>> = operation
+      .params()
+      .iter()
+      .map(|&param| match param {
+        Parameter::Position(addr) if addr >= 0 => Some(addr as u64),
+        Parameter::Relative(offset) => match self.fp.checked_add(offset) {
+          Some(addr) if addr >= 0 => Some(addr as u64),
+          _ => None,
+        },
+        _ => None,
+      })
+      .collect();
+
+    let values = operation
+      .params()
+      .iter()
+      .zip(addresses.iter())
+      .map(|(param, addr)| match addr {
+        Some(addr) => narrow(&self.memory.read(*addr)),
+        None => param.value(),
+      })
+      .collect();
+
+    (operation, addresses, values)
+  }
+
+  fn decode_operation(&self) -> Operation {
+    let word = |addr: u64| narrow(&self.memory.read(addr));
+
+    let words: Vec<i64> = (self.pc..self.pc + 4).map(word).collect();
+    if let Some(instruction) = Instruction::decode(&words) {
+      return Operation::Instruction(instruction);
+    }
+
+    let raw = words[0];
+    let plugin = match self.plugins.get(raw % 100) {
+      Some(plugin) if raw >= 0 => plugin,
+      _ => return Operation::Invalid(raw),
+    };
+
+    let params = (0..plugin.arity() as u32)
+      .map(|index| {
+        let mode = raw / 10i64.checked_pow(index + 2)? % 10;
+        Parameter::from_mode(mode, word(self.pc + 1 + index as u64))
+      })
+      .collect();
+
+    match params {
+      Some(params) => Operation::Plugin {
+        name: plugin.name.clone(),
+        params,
+        write_parameter: plugin.write_parameter(),
+      },
+      None => Operation::Invalid(raw),
+    }
+  }
+
+  // Executes the instruction at pc from the decode cache, decoding it first if
+  // needed. Anything that doesn't decode cleanly goes to the interpreter,
+  // which raises the same faults it always does.
+  fn execute_cached(&mut self) -> Result<Option<Interruption>, Fault> {
+    let decoded = match self.cache.get(self.pc) {
+      Some(decoded) => decoded,
+      None => {
+        // words that don't fit in an i64 can't be decoded, so they're left
+        // to the interpreter
+        let mut words = [0; 4];
+        for (offset, word) in words.iter_mut().enumerate() {
+          match self.memory.read(self.pc + offset as u64).to_i64() {
+            Some(value) => *word = value,
+            None => return self.interpret(),
+          }
+        }
+        match self.cache.decode(self.pc, &words) {
+          Some(decoded) => decoded,
+          None => return self.interpret(),
+        }
+      }
+    };
+
+    let [p1, p2, p3] = decoded.params;
+
+    match decoded.opcode {
+      Opcode::Add => {
+        let value = self.add(&self.load_operand(p1)?, &self.load_operand(p2)?)?;
+        let addr = self.operand_address(p3)?;
+        self.store(addr, value)?;
+        self.pc += 4;
+      }
+      Opcode::Multiply => {
+        let value = self.multiply(&self.load_operand(p1)?, &self.load_operand(p2)?)?;
+        let addr = self.operand_address(p3)?;
+        self.store(addr, value)?;
+        self.pc += 4;
+      }
+      Opcode::Input => {
+        if !self.fill_input()? {
+          self.state = State::Interrupted;
+          return Ok(Some(Interruption::Input));
+        }
+
+        let addr = self.operand_address(p1)?;
+        let value = self
+          .input_buffer
+          .pop_front()
+          .expect("Error reading input buffer");
+        self.store(addr, value)?;
+        self.pc += 2;
+      }
+      Opcode::Output => {
+        let value = self.load_operand(p1)?;
+        self.pc += 2;
+        if self.emit_output(value) {
+          self.state = State::Interrupted;
+          return Ok(Some(Interruption::Output));
+        }
+      }
+      Opcode::JumpIfTrue | Opcode::JumpIfFalse => {
+        let value = self.load_operand(p1)?;
+        let target = self.load_operand(p2)?;
+
+        if (value != W::default()) == (decoded.opcode == Opcode::JumpIfTrue) {
+          self.pc = self.to_address(narrow(&target))?;
+        } else {
+          self.pc += 3;
+        }
+      }
+      Opcode::LessThan | Opcode::Equals => {
+        let a = self.load_operand(p1)?;
+        let b = self.load_operand(p2)?;
+        let addr = self.operand_address(p3)?;
+        let result = match decoded.opcode {
+          Opcode::LessThan => a < b,
+          _ => a == b,
+        };
+        self.store(addr, W::from_i64(i64::from(result)))?;
+        self.pc += 4;
+      }
+      Opcode::AdjustRelativeBase => {
+        let value = self.load_operand(p1)?;
+        self.adjust_relative_base(&value)?;
+        self.pc += 2;
+      }
+      Opcode::Halt => {
+        self.state = State::Halted;
+        return Ok(Some(Interruption::Halt));
+      }
+    }
+
+    Ok(None)
+  }
+
+  fn load_operand(&self, param: Parameter) -> Result<W, Fault> {
+    match param {
+      Parameter::Immediate(value) => Ok(W::from_i64(value)),
+      _ => self.fetch(self.operand_address(param)?),
+    }
+  }
+
+  fn operand_address(&self, param: Parameter) -> Result<u64, Fault> {
+    match param {
+      Parameter::Position(addr) => self.to_address(addr),
+      Parameter::Relative(offset) => self.to_address(self.fp.saturating_add(offset)),
+      Parameter::Immediate(_) => unreachable!("immediate operands have no address"),
+    }
+  }
+
+  // Executes the instruction at pc, returning the interruption it raised, if
+  // any. An input instruction with nothing to read doesn't advance the pc, so
+  // it will be retried once input is available.
+  fn interpret(&mut self) -> Result<Option<Interruption>, Fault> {
+    // words too big for an i64 aren't opcodes of any kind
+    let opcode = self.fetch(self.pc)?.to_i64().unwrap_or(-1);
+
+    match opcode % 100 {
+      1 => {
+        let p1 = self.fetch(self.get_read_addr(0)?)?;
+        let p2 = self.fetch(self.get_read_addr(1)?)?;
+        let addr = self.get_write_addr(2)?;
+
+        let value = self.add(&p1, &p2)?;
+        self.store(addr, value)?;
+        self.pc += 4;
+      }
+      2 => {
+        let p1 = self.fetch(self.get_read_addr(0)?)?;
+        let p2 = self.fetch(self.get_read_addr(1)?)?;
+        let addr = self.get_write_addr(2)?;
+
+        let value = self.multiply(&p1, &p2)?;
+        self.store(addr, value)?;
+        self.pc += 4;
+      }
+      3 => {
+        // Opcode 3 takes a single integer as input and saves it
+        // to the position given by its only parameter. For example,
+        // the instruction 3,50 would take an input value and
+        // store it at address 50.
+
+        // input buffer exausted, interrupt to wait for input
+        if !self.fill_input()? {
+          self.state = State::Interrupted;
+          return Ok(Some(Interruption::Input));
+        }
+
+        let addr = self.get_write_addr(0)?;
+
+        let value = self
+          .input_buffer
+          .pop_front()
+          .expect("Error reading input buffer");
+
+        self.store(addr, value)?;
+        self.pc += 2;
+      }
+      4 => {
+        // Opcode 4 outputs the value of its only parameter. For
+        // example, the instruction 4,50 would output the value at
+        // address 50.
+
+        let value = self.fetch(self.get_read_addr(0)?)?;
+
+        self.pc += 2;
+        if self.emit_output(value) {
+          self.state = State::Interrupted;
+          return Ok(Some(Interruption::Output));
+        }
+      }
+      5 => {
+        // Opcode 5 is jump-if-true: if the first parameter is
+        // non-zero, it sets the instruction pointer to the value
+        // from the second parameter. Otherwise, it does nothing.
+
+        let value = self.fetch(self.get_read_addr(0)?)?;
+        let addr = self.fetch(self.get_read_addr(1)?)?;
+
+        if value != W::default() {
+          self.pc = self.to_address(narrow(&addr))?;
+        } else {
+          self.pc += 3;
+        }
+      }
+      6 => {
+        // Opcode 6 is jump-if-false: if the first parameter is
+        // zero, it sets the instruction pointer to the value
+        // from the second parameter. Otherwise, it does nothing.
+
+        let value = self.fetch(self.get_read_addr(0)?)?;
+        let addr = self.fetch(self.get_read_addr(1)?)?;
+
+        if value == W::default() {
+          self.pc = self.to_address(narrow(&addr))?;
+        } else {
+          self.pc += 3;
+        }
+      }
+      7 => {
+        // Opcode 7 is less than: if the first parameter is less
+        // than the second parameter, it stores 1 in the position
+        // given by the third parameter. Otherwise, it stores 0.
+
+        let p1 = self.fetch(self.get_read_addr(0)?)?;
+        let p2 = self.fetch(self.get_read_addr(1)?)?;
+        let addr = self.get_write_addr(2)?;
+
+        self.store(addr, W::from_i64(if p1 < p2 { 1 } else { 0 }))?;
+        self.pc += 4;
+      }
+      8 => {
+        // Opcode 8 is equals: if the first parameter is equal to
+        // the second parameter, it stores 1 in the position given
+        // by the third parameter. Otherwise, it stores 0.
+
+        let p1 = self.fetch(self.get_read_addr(0)?)?;
+        let p2 = self.fetch(self.get_read_addr(1)?)?;
+        let addr = self.get_write_addr(2)?;
+
+        self.store(addr, W::from_i64(if p1 == p2 { 1 } else { 0 }))?;
+        self.pc += 4;
+      }
+      9 => {
+        // Opcode 9 adjusts the relative base by the value of its only
+        // parameter. The relative base increases (or decreases, if the
+        // value is negative) by the value of the parameter.
+
+        let value = self.fetch(self.get_read_addr(0)?)?;
+
+        self.adjust_relative_base(&value)?;
+        self.pc += 2;
+      }
+      99 => {
+        self.state = State::Halted;
+        return Ok(Some(Interruption::Halt));
+      }
+      code => {
+        if let Some(plugin) = self.plugins.get(code).cloned() {
+          return self.execute_plugin(&plugin);
+        }
+
+        return Err(Fault::UnknownOpcode {
+          pc: self.pc,
+          fp: self.fp,
+          instruction: self.instruction(),
+        });
+      }
+    }
+
+    Ok(None)
+  }
+
+  fn execute_plugin(&mut self, plugin: &Plugin<W>) -> Result<Option<Interruption>, Fault> {
+    let mut values = Vec::with_capacity(plugin.arity());
+    let mut target = None;
+
+    for (index, access) in plugin.params.iter().enumerate() {
+      let addr = match access {
+        Access::Read => self.get_read_addr(index as u64)?,
+        Access::Write => {
+          let addr = self.get_write_addr(index as u64)?;
+          target = Some(addr);
+          addr
+        }
+      };
+      values.push(self.fetch(addr)?);
+    }
+
+    let size = plugin.arity() as u64 + 1;
+
+    match (plugin.handler)(&values) {
+      Effect::Continue => self.pc += size,
+      Effect::Store(value) => {
+        let addr = target.ok_or(Fault::NoWriteParameter {
+          pc: self.pc,
+          fp: self.fp,
+          instruction: self.instruction(),
+        })?;
+        self.store(addr, value)?;
+        self.pc += size;
+      }
+      Effect::Jump(addr) => self.pc = addr,
+      Effect::Output(value) => {
+        self.pc += size;
+        if self.emit_output(value) {
+          self.state = State::Interrupted;
+          return Ok(Some(Interruption::Output));
+        }
+      }
+      Effect::Halt => {
+        self.state = State::Halted;
+        return Ok(Some(Interruption::Halt));
+      }
+    }
+
+    Ok(None)
+  }
+}
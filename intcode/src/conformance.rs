@@ -0,0 +1,282 @@
+use crate::computer::{parse, Interruption};
+use crate::machine::Machine;
+use std::fmt;
+
+// An example program from the puzzle statements, what to feed it, and what
+// it must output and leave in memory: the words of the program itself, as
+// they are once it halts.
+#[derive(Debug, Clone, Copy)]
+pub struct Example {
+  pub name: &'static str,
+  pub program: &'static str,
+  pub inputs: &'static [i64],
+  pub outputs: &'static [i64],
+  pub memory: &'static str,
+}
+
+const COMPARE: &str = "3,21,1008,21,8,20,1005,20,22,107,8,21,20,1006,20,31,1106,0,36,98,0,0,\
+                       1002,21,125,20,4,20,1105,1,46,104,999,1105,1,46,1101,1000,1,20,4,20,\
+                       1105,1,46,98,99";
+
+const QUINE: &str = "109,1,204,-1,1001,100,1,100,1008,100,16,101,1006,101,0,99";
+
+pub const EXAMPLES: &[Example] = &[
+  Example {
+    name: "day02 add",
+    program: "1,0,0,0,99",
+    inputs: &[],
+    outputs: &[],
+    memory: "2,0,0,0,99",
+  },
+  Example {
+    name: "day02 multiply",
+    program: "2,3,0,3,99",
+    inputs: &[],
+    outputs: &[],
+    memory: "2,3,0,6,99",
+  },
+  Example {
+    name: "day02 multiply past the halt",
+    program: "2,4,4,5,99,0",
+    inputs: &[],
+    outputs: &[],
+    memory: "2,4,4,5,99,9801",
+  },
+  Example {
+    name: "day02 overwritten halt",
+    program: "1,1,1,4,99,5,6,0,99",
+    inputs: &[],
+    outputs: &[],
+    memory: "30,1,1,4,2,5,6,0,99",
+  },
+  Example {
+    name: "day02 walkthrough",
+    program: "1,9,10,3,2,3,11,0,99,30,40,50",
+    inputs: &[],
+    outputs: &[],
+    memory: "3500,9,10,70,2,3,11,0,99,30,40,50",
+  },
+  Example {
+    name: "day05 echo",
+    program: "3,0,4,0,99",
+    inputs: &[42],
+    outputs: &[42],
+    memory: "42,0,4,0,99",
+  },
+  Example {
+    name: "day05 parameter modes",
+    program: "1002,4,3,4,33",
+    inputs: &[],
+    outputs: &[],
+    memory: "1002,4,3,4,99",
+  },
+  Example {
+    name: "day05 negative immediate",
+    program: "1101,100,-1,4,0",
+    inputs: &[],
+    outputs: &[],
+    memory: "1101,100,-1,4,99",
+  },
+  Example {
+    name: "day05 equal to 8, position mode",
+    program: "3,9,8,9,10,9,4,9,99,-1,8",
+    inputs: &[8],
+    outputs: &[1],
+    memory: "3,9,8,9,10,9,4,9,99,1,8",
+  },
+  Example {
+    name: "day05 not equal to 8, position mode",
+    program: "3,9,8,9,10,9,4,9,99,-1,8",
+    inputs: &[5],
+    outputs: &[0],
+    memory: "3,9,8,9,10,9,4,9,99,0,8",
+  },
+  Example {
+    name: "day05 less than 8, position mode",
+    program: "3,9,7,9,10,9,4,9,99,-1,8",
+    inputs: &[7],
+    outputs: &[1],
+    memory: "3,9,7,9,10,9,4,9,99,1,8",
+  },
+  Example {
+    name: "day05 not less than 8, position mode",
+    program: "3,9,7,9,10,9,4,9,99,-1,8",
+    inputs: &[8],
+    outputs: &[0],
+    memory: "3,9,7,9,10,9,4,9,99,0,8",
+  },
+  Example {
+    name: "day05 equal to 8, immediate mode",
+    program: "3,3,1108,-1,8,3,4,3,99",
+    inputs: &[8],
+    outputs: &[1],
+    memory: "3,3,1108,1,8,3,4,3,99",
+  },
+  Example {
+    name: "day05 not equal to 8, immediate mode",
+    program: "3,3,1108,-1,8,3,4,3,99",
+    inputs: &[9],
+    outputs: &[0],
+    memory: "3,3,1108,0,8,3,4,3,99",
+  },
+  Example {
+    name: "day05 less than 8, immediate mode",
+    program: "3,3,1107,-1,8,3,4,3,99",
+    inputs: &[-3],
+    outputs: &[1],
+    memory: "3,3,1107,1,8,3,4,3,99",
+  },
+  Example {
+    name: "day05 not less than 8, immediate mode",
+    program: "3,3,1107,-1,8,3,4,3,99",
+    inputs: &[8],
+    outputs: &[0],
+    memory: "3,3,1107,0,8,3,4,3,99",
+  },
+  Example {
+    name: "day05 jump on zero, position mode",
+    program: "3,12,6,12,15,1,13,14,13,4,13,99,-1,0,1,9",
+    inputs: &[0],
+    outputs: &[0],
+    memory: "3,12,6,12,15,1,13,14,13,4,13,99,0,0,1,9",
+  },
+  Example {
+    name: "day05 jump on non-zero, position mode",
+    program: "3,12,6,12,15,1,13,14,13,4,13,99,-1,0,1,9",
+    inputs: &[5],
+    outputs: &[1],
+    memory: "3,12,6,12,15,1,13,14,13,4,13,99,5,1,1,9",
+  },
+  Example {
+    name: "day05 jump on zero, immediate mode",
+    program: "3,3,1105,-1,9,1101,0,0,12,4,12,99,1",
+    inputs: &[0],
+    outputs: &[0],
+    memory: "3,3,1105,0,9,1101,0,0,12,4,12,99,0",
+  },
+  Example {
+    name: "day05 jump on non-zero, immediate mode",
+    program: "3,3,1105,-1,9,1101,0,0,12,4,12,99,1",
+    inputs: &[5],
+    outputs: &[1],
+    memory: "3,3,1105,5,9,1101,0,0,12,4,12,99,1",
+  },
+  Example {
+    name: "day05 below 8",
+    program: COMPARE,
+    inputs: &[7],
+    outputs: &[999],
+    memory: "3,21,1008,21,8,20,1005,20,22,107,8,21,20,1006,20,31,1106,0,36,98,0,7,\
+               1002,21,125,20,4,20,1105,1,46,104,999,1105,1,46,1101,1000,1,20,4,20,\
+               1105,1,46,98,99",
+  },
+  Example {
+    name: "day05 equal to 8",
+    program: COMPARE,
+    inputs: &[8],
+    outputs: &[1000],
+    memory: "3,21,1008,21,8,20,1005,20,22,107,8,21,20,1006,20,31,1106,0,36,98,1000,8,\
+               1002,21,125,20,4,20,1105,1,46,104,999,1105,1,46,1101,1000,1,20,4,20,\
+               1105,1,46,98,99",
+  },
+  Example {
+    name: "day05 above 8",
+    program: COMPARE,
+    inputs: &[9],
+    outputs: &[1001],
+    memory: "3,21,1008,21,8,20,1005,20,22,107,8,21,20,1006,20,31,1106,0,36,98,1001,9,\
+               1002,21,125,20,4,20,1105,1,46,104,999,1105,1,46,1101,1000,1,20,4,20,\
+               1105,1,46,98,99",
+  },
+  Example {
+    name: "day09 quine",
+    program: QUINE,
+    inputs: &[],
+    outputs: &[
+      109, 1, 204, -1, 1001, 100, 1, 100, 1008, 100, 16, 101, 1006, 101, 0, 99,
+    ],
+    memory: QUINE,
+  },
+  Example {
+    name: "day09 16-digit number",
+    program: "1102,34915192,34915192,7,4,7,99,0",
+    inputs: &[],
+    outputs: &[1219070632396864],
+    memory: "1102,34915192,34915192,7,4,7,99,1219070632396864",
+  },
+  Example {
+    name: "day09 large number",
+    program: "104,1125899906842624,99",
+    inputs: &[],
+    outputs: &[1125899906842624],
+    memory: "104,1125899906842624,99",
+  },
+];
+
+// An example a machine got wrong, and how.
+#[derive(Debug, Clone)]
+pub struct Failure {
+  pub example: &'static str,
+  pub message: String,
+}
+
+impl fmt::Display for Failure {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    write!(f, "{}: {}", self.example, self.message)
+  }
+}
+
+// Runs every example on a machine `new_machine` makes for it, loaded with
+// its program, and returns the ones it got wrong.
+pub fn check_conformance<F>(mut new_machine: F) -> Vec<Failure>
+where
+  F: FnMut(&Example) -> Box<dyn Machine>,
+{
+  let mut failures = Vec::new();
+
+  for example in EXAMPLES.iter() {
+    let mut machine = new_machine(example);
+    let mut fail = |message: String| {
+      failures.push(Failure {
+        example: example.name,
+        message,
+      })
+    };
+
+    for &value in example.inputs.iter() {
+      machine.input(value);
+    }
+
+    let mut outputs = Vec::new();
+    let finished = loop {
+      match machine.run() {
+        Interruption::Output => outputs.extend(machine.output()),
+        Interruption::Halt => break true,
+        interruption => {
+          fail(format!("stopped with {:?}", interruption));
+          break false;
+        }
+      }
+    };
+    if !finished {
+      continue;
+    }
+
+    if outputs != example.outputs {
+      fail(format!(
+        "output {:?}, expected {:?}",
+        outputs, example.outputs
+      ));
+    }
+
+    let expected = parse(example.memory);
+    let memory: Vec<i64> = (0..expected.len() as u64)
+      .map(|address| machine.read(address))
+      .collect();
+    if memory != expected {
+      fail(format!("memory {:?}, expected {:?}", memory, expected));
+    }
+  }
+
+  failures
+}
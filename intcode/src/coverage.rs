@@ -0,0 +1,180 @@
+use crate::disassembler::{disassemble, Line};
+use crate::instruction::Opcode;
+use crate::tracer::{Operation, TraceEvent};
+use std::collections::{BTreeMap, BTreeSet};
+use std::fmt::Write;
+
+const MARGIN: usize = 4;
+
+// Which way a conditional jump went, over every time it was executed.
+#[derive(Debug, PartialEq, Eq, Copy, Clone, Default)]
+pub struct Branch {
+  pub taken: bool,
+  pub not_taken: bool,
+}
+
+// Addresses the program executed as instructions, and the ones it read or
+// wrote as data. Coverage from several runs can be merged together.
+#[derive(Debug, Clone, Default)]
+pub struct Coverage {
+  executed: BTreeMap<u64, usize>,
+  reads: BTreeSet<u64>,
+  writes: BTreeSet<u64>,
+  branches: BTreeMap<u64, Branch>,
+}
+
+impl Coverage {
+  pub fn new() -> Coverage {
+    Coverage::default()
+  }
+
+  pub fn record(&mut self, event: &TraceEvent) {
+    let write_param = event.operation.write_parameter();
+
+    self.executed.insert(event.pc, event.operation.size());
+
+    for (index, addr) in event.addresses.iter().enumerate() {
+      if let (Some(addr), false) = (addr, Some(index) == write_param) {
+        self.reads.insert(*addr);
+      }
+    }
+    if let Some((addr, _)) = event.write {
+      self.writes.insert(addr);
+    }
+
+    let opcode = match &event.operation {
+      Operation::Instruction(instruction) if instruction.opcode.is_jump() => instruction.opcode,
+      _ => return,
+    };
+
+    let branch = self.branches.entry(event.pc).or_default();
+    if (event.values[0] != 0) == (opcode == Opcode::JumpIfTrue) {
+      branch.taken = true;
+    } else {
+      branch.not_taken = true;
+    }
+  }
+
+  pub fn merge(&mut self, other: &Coverage) {
+    self.executed.extend(other.executed.iter());
+    self.reads.extend(other.reads.iter());
+    self.writes.extend(other.writes.iter());
+
+    for (&addr, other) in other.branches.iter() {
+      let branch = self.branches.entry(addr).or_default();
+      branch.taken |= other.taken;
+      branch.not_taken |= other.not_taken;
+    }
+  }
+
+  // Whether an instruction starting at `addr` was executed.
+  pub fn executed(&self, addr: u64) -> bool {
+    self.executed.contains_key(&addr)
+  }
+
+  // Whether `addr` is part of any executed instruction.
+  pub fn is_code(&self, addr: u64) -> bool {
+    match self.executed.range(..=addr).next_back() {
+      Some((&start, &size)) => addr < start + size as u64,
+      None => false,
+    }
+  }
+
+  pub fn was_read(&self, addr: u64) -> bool {
+    self.reads.contains(&addr)
+  }
+
+  pub fn was_written(&self, addr: u64) -> bool {
+    self.writes.contains(&addr)
+  }
+
+  pub fn branch(&self, addr: u64) -> Option<Branch> {
+    self.branches.get(&addr).copied()
+  }
+
+  // Disassembles `program` with a coverage margin in front of every line:
+  //
+  //   +     instruction executed
+  //   -     instruction never executed
+  //   T/F   jump only ever taken (T) or only ever fell through (F)
+  //   x     executed, although the disassembler took it for data
+  //   r/w   read or written as data
+  pub fn annotate(&self, program: &[i64]) -> String {
+    let listing = disassemble(program);
+
+    let mut statements = 0;
+    let mut covered = 0;
+    for line in listing.lines.iter() {
+      if let Line::Instruction { address, .. } = line {
+        statements += 1;
+        if self.executed(*address) {
+          covered += 1;
+        }
+      }
+    }
+
+    let complete = self
+      .branches
+      .values()
+      .filter(|branch| branch.taken && branch.not_taken)
+      .count();
+
+    let mut text = String::new();
+    writeln!(
+      text,
+      "; {} of {} instructions executed ({:.1}%), {} of {} executed branches went both ways",
+      covered,
+      statements,
+      100.0 * covered as f64 / statements.max(1) as f64,
+      complete,
+      self.branches.len()
+    )
+    .unwrap();
+
+    listing
+      .write_with_margin(&mut text, MARGIN, |line| self.margin(line))
+      .unwrap();
+
+    text
+  }
+
+  fn margin(&self, line: &Line) -> String {
+    let (start, size) = match line {
+      Line::Instruction {
+        address,
+        instruction,
+      } => (*address, instruction.size()),
+      Line::Data { address, values } => (*address, values.len()),
+    };
+    let words = start..start + size as u64;
+
+    let execution = match line {
+      Line::Instruction { address, .. } => match self.branch(*address) {
+        Some(Branch {
+          taken: true,
+          not_taken: false,
+        }) => 'T',
+        Some(Branch {
+          taken: false,
+          not_taken: true,
+        }) => 'F',
+        _ if self.executed(*address) => '+',
+        _ => '-',
+      },
+      Line::Data { .. } if words.clone().any(|addr| self.executed(addr)) => 'x',
+      Line::Data { .. } => ' ',
+    };
+    let read = if words.clone().any(|addr| self.was_read(addr)) {
+      'r'
+    } else {
+      ' '
+    };
+    let written = if words.clone().any(|addr| self.was_written(addr)) {
+      'w'
+    } else {
+      ' '
+    };
+
+    format!("{}{}{}", execution, read, written)
+  }
+}
@@ -0,0 +1,333 @@
+use crate::computer::{Computer, Interruption, State, MEMORY_LIMIT};
+use crate::instruction::{Instruction, Opcode};
+use std::collections::BTreeSet;
+
+// Most words `mem` shows at once.
+const MAX_WORDS: u64 = 1024;
+// Most instructions one `continue` runs before handing control back, so a
+// program stuck in a loop can't hang the debugger.
+const MAX_CONTINUE: u64 = 10_000_000;
+// Instructions run between breakpoint checks when there are none to check.
+const SLICE: u64 = 10_000;
+
+const HELP: &str = "\
+commands:
+  break <addr>         stop before executing the instruction at <addr>
+  break op <opcode>    stop before executing any <opcode> (mnemonic or number)
+  delete <addr>        remove a breakpoint (`delete op <opcode>` for opcodes)
+  breakpoints          list breakpoints
+  step [n]             execute n instructions (default 1)
+  continue             run until a breakpoint, input is needed, halt or fault
+                       (or for at most 10000000 instructions)
+  regs                 show pc, fp and state
+  io                   show the input and output buffers
+  input <v>...         queue input values
+  mem <addr> [n]       show n words of memory (default 8, at most 1024)
+  set <addr> <value>   write a value to memory
+  disasm [addr] [n]    disassemble n instructions (default 8, from pc)
+  quit                 leave the debugger";
+
+pub struct Debugger {
+  computer: Computer,
+  breakpoints: BTreeSet<u64>,
+  opcode_breakpoints: BTreeSet<Opcode>,
+}
+
+impl Debugger {
+  pub fn new(computer: Computer) -> Debugger {
+    Debugger {
+      computer,
+      breakpoints: BTreeSet::new(),
+      opcode_breakpoints: BTreeSet::new(),
+    }
+  }
+
+  pub fn computer(&self) -> &Computer {
+    &self.computer
+  }
+
+  // Executes a command line and returns what should be shown to the user,
+  // or `None` if the user asked to quit.
+  pub fn execute(&mut self, line: &str) -> Option<String> {
+    let words: Vec<&str> = line.split_whitespace().collect();
+
+    let result = match words.as_slice() {
+      [] => Ok(String::new()),
+      ["quit"] | ["q"] => return None,
+      ["help"] | ["h"] => Ok(String::from(HELP)),
+      ["break", "op", opcode] | ["b", "op", opcode] => self.break_opcode(opcode),
+      ["break", addr] | ["b", addr] => parse_address(addr).map(|addr| {
+        self.breakpoints.insert(addr);
+        format!("breakpoint at {}", addr)
+      }),
+      ["delete", "op", opcode] => parse_opcode(opcode).map(|opcode| {
+        self.opcode_breakpoints.remove(&opcode);
+        format!("removed breakpoint on {}", opcode.mnemonic())
+      }),
+      ["delete", addr] => parse_address(addr).map(|addr| {
+        self.breakpoints.remove(&addr);
+        format!("removed breakpoint at {}", addr)
+      }),
+      ["breakpoints"] => Ok(self.list_breakpoints()),
+      ["step"] | ["s"] => Ok(self.step(1)),
+      ["step", n] | ["s", n] => parse_number(n).map(|n| self.step(n.max(0) as usize)),
+      ["continue"] | ["c"] => Ok(self.resume()),
+      ["regs"] | ["r"] => Ok(self.registers()),
+      ["io"] => Ok(self.io()),
+      ["input", values @ ..] | ["i", values @ ..] if !values.is_empty() => self.queue_input(values),
+      ["mem", addr] | ["x", addr] => parse_address(addr).map(|addr| self.memory(addr, 8)),
+      ["mem", addr, n] | ["x", addr, n] => parse_address(addr)
+        .and_then(|addr| parse_number(n).map(|n| self.memory(addr, n.max(0) as u64))),
+      ["set", addr, value] => parse_address(addr).and_then(|addr| {
+        parse_number(value).and_then(|value| {
+          self
+            .computer
+            .write(addr, value)
+            .map_err(|fault| fault.to_string())?;
+          Ok(format!("{}: {}", addr, value))
+        })
+      }),
+      ["disasm"] | ["l"] => Ok(self.disassemble(self.computer.pc(), 8)),
+      ["disasm", addr] | ["l", addr] => parse_address(addr).map(|addr| self.disassemble(addr, 8)),
+      ["disasm", addr, n] | ["l", addr, n] => parse_address(addr)
+        .and_then(|addr| parse_number(n).map(|n| self.disassemble(addr, n.max(0) as usize))),
+      _ => Err(format!("unknown command '{}', try `help`", line.trim())),
+    };
+
+    Some(result.unwrap_or_else(|err| err))
+  }
+
+  fn break_opcode(&mut self, opcode: &str) -> Result<String, String> {
+    let opcode = parse_opcode(opcode)?;
+    self.opcode_breakpoints.insert(opcode);
+    Ok(format!("breakpoint on {}", opcode.mnemonic()))
+  }
+
+  fn list_breakpoints(&self) -> String {
+    let mut lines: Vec<String> = self
+      .breakpoints
+      .iter()
+      .map(|addr| format!("  at {}", addr))
+      .collect();
+    lines.extend(
+      self
+        .opcode_breakpoints
+        .iter()
+        .map(|opcode| format!("  on {}", opcode.mnemonic())),
+    );
+
+    if lines.is_empty() {
+      String::from("no breakpoints")
+    } else {
+      lines.join("\n")
+    }
+  }
+
+  fn at_breakpoint(&self) -> bool {
+    let pc = self.computer.pc();
+    if self.breakpoints.contains(&pc) {
+      return true;
+    }
+
+    match Opcode::from_code(self.computer.read(pc) % 100) {
+      Some(opcode) => self.opcode_breakpoints.contains(&opcode),
+      None => false,
+    }
+  }
+
+  fn step(&mut self, count: usize) -> String {
+    let mut lines = Vec::new();
+
+    for _ in 0..count {
+      if let Some(reason) = self.execute_one(&mut lines) {
+        lines.push(reason);
+        break;
+      }
+    }
+
+    lines.push(self.location(self.computer.pc()));
+    lines.join("\n")
+  }
+
+  fn resume(&mut self) -> String {
+    let mut lines = Vec::new();
+    let start = self.computer.executed();
+    let slice = if self.breakpoints.is_empty() && self.opcode_breakpoints.is_empty() {
+      SLICE
+    } else {
+      1
+    };
+    let mut first = true;
+
+    loop {
+      // don't stop on the breakpoint we're currently sitting on
+      if !first && self.at_breakpoint() {
+        lines.push(format!("breakpoint at {}", self.computer.pc()));
+        break;
+      }
+      first = false;
+
+      let executed = self.computer.executed() - start;
+      if executed >= MAX_CONTINUE {
+        lines.push(format!(
+          "still running after {} instructions, `continue` to keep going",
+          executed
+        ));
+        break;
+      }
+
+      if let State::Halted = self.computer.get_state() {
+        lines.push(String::from("program has halted"));
+        break;
+      }
+
+      let interruption = self.computer.run_for(slice.min(MAX_CONTINUE - executed));
+      if let Some(reason) = self.interrupted(interruption, &mut lines) {
+        lines.push(reason);
+        break;
+      }
+    }
+
+    lines.push(self.location(self.computer.pc()));
+    lines.join("\n")
+  }
+
+  // Executes one instruction, returning why execution should stop, if it
+  // should. Outputs are reported as they happen and left in the buffer.
+  fn execute_one(&mut self, lines: &mut Vec<String>) -> Option<String> {
+    if let State::Halted = self.computer.get_state() {
+      return Some(String::from("program has halted"));
+    }
+
+    match self.computer.step() {
+      None => None,
+      Some(interruption) => self.interrupted(interruption, lines),
+    }
+  }
+
+  // Reports an interruption, returning why execution should stop, if it
+  // should. Running out of instructions isn't a reason to stop.
+  fn interrupted(&self, interruption: Interruption, lines: &mut Vec<String>) -> Option<String> {
+    match interruption {
+      Interruption::Output => {
+        if let Some(value) = self.computer.pending_output().back() {
+          lines.push(format!("output: {}", value));
+        }
+        None
+      }
+      Interruption::Input => Some(String::from(
+        "waiting for input, queue values with `input <v>...`",
+      )),
+      Interruption::Halt => Some(String::from("halted")),
+      Interruption::Fault(fault) => Some(format!("fault: {}", fault)),
+      Interruption::BudgetExhausted => None,
+    }
+  }
+
+  fn registers(&self) -> String {
+    format!(
+      "pc: {}\nfp: {}\nstate: {:?}\nexecuted: {}",
+      self.computer.pc(),
+      self.computer.fp(),
+      self.computer.get_state(),
+      self.computer.executed()
+    )
+  }
+
+  fn io(&self) -> String {
+    format!(
+      "input: {:?}\noutput: {:?}",
+      self.computer.pending_input(),
+      self.computer.pending_output()
+    )
+  }
+
+  fn queue_input(&mut self, values: &[&str]) -> Result<String, String> {
+    let values = values
+      .iter()
+      .map(|value| parse_number(value))
+      .collect::<Result<Vec<i64>, String>>()?;
+
+    for &value in values.iter() {
+      self.computer.input(value);
+    }
+
+    Ok(format!("input: {:?}", self.computer.pending_input()))
+  }
+
+  fn memory(&self, addr: u64, count: u64) -> String {
+    let mut lines = Vec::new();
+
+    let last = addr.saturating_add(count.min(MAX_WORDS)).min(MEMORY_LIMIT);
+    for row in (addr..last).step_by(8) {
+      let end = (row + 8).min(last);
+      let values: Vec<String> = (row..end)
+        .map(|a| self.computer.read(a).to_string())
+        .collect();
+      lines.push(format!("{}: {}", row, values.join(" ")));
+    }
+
+    lines.join("\n")
+  }
+
+  fn disassemble(&self, addr: u64, count: usize) -> String {
+    let mut lines = Vec::new();
+    let mut addr = addr;
+
+    for _ in 0..count {
+      if addr >= MEMORY_LIMIT {
+        break;
+      }
+      lines.push(self.location(addr));
+      addr += match self.decode(addr) {
+        Some(instruction) => instruction.size() as u64,
+        None => 1,
+      };
+    }
+
+    lines.join("\n")
+  }
+
+  fn decode(&self, addr: u64) -> Option<Instruction> {
+    let words: Vec<i64> = (addr..addr.saturating_add(4).min(MEMORY_LIMIT))
+      .map(|a| self.computer.read(a))
+      .collect();
+    Instruction::decode(&words)
+  }
+
+  fn location(&self, addr: u64) -> String {
+    let marker = if addr == self.computer.pc() {
+      "=>"
+    } else {
+      "  "
+    };
+
+    match self.decode(addr) {
+      Some(instruction) => format!("{} {}: {}", marker, addr, instruction),
+      None => format!("{} {}: data {}", marker, addr, self.computer.read(addr)),
+    }
+  }
+}
+
+fn parse_number(text: &str) -> Result<i64, String> {
+  text
+    .parse()
+    .map_err(|_| format!("invalid number '{}'", text))
+}
+
+fn parse_address(text: &str) -> Result<u64, String> {
+  match text.parse() {
+    Ok(addr) if addr < MEMORY_LIMIT => Ok(addr),
+    Ok(addr) => Err(format!("address {} is out of range", addr)),
+    Err(_) => Err(format!("invalid address '{}'", text)),
+  }
+}
+
+fn parse_opcode(text: &str) -> Result<Opcode, String> {
+  let opcode = match text.parse() {
+    Ok(code) => Opcode::from_code(code),
+    Err(_) => Opcode::from_mnemonic(text),
+  };
+
+  opcode.ok_or_else(|| format!("unknown opcode '{}'", text))
+}
@@ -0,0 +1,629 @@
+use crate::flow::{analyze, constant_result, EdgeKind, Exit, FlowGraph};
+use crate::instruction::{Instruction, Opcode, Parameter};
+use std::collections::{BTreeMap, BTreeSet};
+use std::fmt::Write;
+
+// A condition a jump tests, e.g. `x < 10` or `flag != 0`.
+#[derive(Debug, Clone)]
+struct Condition {
+  left: String,
+  op: &'static str,
+  right: String,
+}
+
+impl Condition {
+  fn negate(&self) -> Condition {
+    let op = match self.op {
+      "<" => ">=",
+      ">=" => "<",
+      "==" => "!=",
+      _ => "==",
+    };
+
+    Condition {
+      left: self.left.clone(),
+      op,
+      right: self.right.clone(),
+    }
+  }
+
+  fn render(&self) -> String {
+    format!("{} {} {}", self.left, self.op, self.right)
+  }
+}
+
+// A call site: the callee, and the instructions storing the arguments in
+// [rb+1], [rb+2], ... right before the call.
+#[derive(Debug, Clone)]
+struct Call {
+  target: u64,
+  args: BTreeMap<i64, u64>,
+}
+
+// A function found from its entry point. `frame` is what its prologue adds
+// to the relative base: the slots below the new base hold the return
+// address, then the parameters, then the locals.
+#[derive(Debug)]
+struct Function {
+  entry: u64,
+  frame: i64,
+  params: i64,
+  body: Vec<u64>,
+}
+
+// Decompiles a program to pseudo-code, one function for the code reachable
+// from address 0 and one for each call target. The usual idioms of compiled
+// AoC programs are folded away: frame setup and teardown with `arb`, return
+// addresses and arguments pushed before a call, and flags computed by
+// `lt`/`eq` only to be tested by the next jump. Backward jumps become
+// do/while loops and forward ones if/else blocks where they nest; anything
+// else is left as a goto.
+pub fn decompile(program: &[i64]) -> String {
+  Decompiler::new(program).render()
+}
+
+struct Decompiler {
+  graph: FlowGraph,
+  instructions: BTreeMap<u64, Instruction>,
+  // every instruction with an edge out of it, i.e. the last one of a block
+  successors: BTreeMap<u64, Vec<(u64, EdgeKind)>>,
+  exits: BTreeMap<u64, Exit>,
+  calls: BTreeMap<u64, Call>,
+  // instructions folded into something else, so they print nothing
+  folded: BTreeSet<u64>,
+  conditions: BTreeMap<u64, Condition>,
+  modified: BTreeSet<u64>,
+  functions: Vec<Function>,
+}
+
+impl Decompiler {
+  fn new(program: &[i64]) -> Decompiler {
+    let graph = analyze(program);
+    let mut instructions = BTreeMap::new();
+    let mut successors = BTreeMap::new();
+    let mut exits = BTreeMap::new();
+
+    for block in graph.blocks.values() {
+      for (address, instruction) in block.instructions.iter() {
+        instructions.insert(*address, instruction.clone());
+      }
+
+      if let Some(&(last, _)) = block.instructions.last() {
+        let edges = graph
+          .edges
+          .iter()
+          .filter(|edge| edge.from == block.start)
+          .map(|edge| (edge.to, edge.kind))
+          .collect();
+        successors.insert(last, edges);
+        exits.insert(last, block.exit);
+      }
+    }
+
+    let modified = graph
+      .modifications
+      .iter()
+      .map(|modification| modification.address)
+      .collect();
+
+    let mut decompiler = Decompiler {
+      graph,
+      instructions,
+      successors,
+      exits,
+      calls: BTreeMap::new(),
+      folded: BTreeSet::new(),
+      conditions: BTreeMap::new(),
+      modified,
+      functions: Vec::new(),
+    };
+
+    decompiler.find_functions();
+    decompiler.fold_calls();
+    decompiler.fold_frames();
+    decompiler.fold_conditions();
+    decompiler
+  }
+
+  fn find_functions(&mut self) {
+    let mut entries = BTreeSet::new();
+    entries.insert(0);
+    entries.extend(
+      self
+        .graph
+        .edges
+        .iter()
+        .filter(|edge| edge.kind == EdgeKind::Call)
+        .map(|edge| edge.to),
+    );
+
+    for &entry in entries.iter() {
+      let mut body = BTreeSet::new();
+      let mut pending = vec![entry];
+
+      while let Some(address) = pending.pop() {
+        if (address != entry && entries.contains(&address))
+          || !self.instructions.contains_key(&address)
+          || !body.insert(address)
+        {
+          continue;
+        }
+
+        match self.successors.get(&address) {
+          Some(edges) => pending.extend(
+            edges
+              .iter()
+              .filter(|&&(_, kind)| kind != EdgeKind::Call)
+              .map(|&(to, _)| to),
+          ),
+          None => pending.push(address + self.instructions[&address].size() as u64),
+        }
+      }
+
+      let frame = match self.instructions.get(&entry) {
+        Some(Instruction {
+          opcode: Opcode::AdjustRelativeBase,
+          params,
+        }) if entry != 0 => match params[0] {
+          Parameter::Immediate(frame) if frame > 0 => frame,
+          _ => 0,
+        },
+        _ => 0,
+      };
+
+      self.functions.push(Function {
+        entry,
+        frame,
+        params: 0,
+        body: body.into_iter().collect(),
+      });
+    }
+  }
+
+  // Folds the return address and the arguments stored right before each call
+  // into the call, and works out how many parameters each function takes.
+  fn fold_calls(&mut self) {
+    for (&address, &exit) in self.exits.iter() {
+      if exit != Exit::Call {
+        continue;
+      }
+
+      let target = match known_target(&self.instructions[&address]) {
+        Some(target) => target,
+        None => continue,
+      };
+      let mut call = Call {
+        target,
+        args: BTreeMap::new(),
+      };
+
+      // the return address store is always right before the jump
+      let mut cursor = address - 4;
+      self.folded.insert(cursor);
+
+      while let Some((&previous, instruction)) = self.instructions.range(..cursor).next_back() {
+        if previous + instruction.size() as u64 != cursor || self.exits.contains_key(&previous) {
+          break;
+        }
+
+        let index = match instruction.opcode.write_parameter() {
+          Some(index) => index,
+          None => break,
+        };
+        let slot = match instruction.params[index] {
+          Parameter::Relative(slot) if slot > 0 && !call.args.contains_key(&slot) => slot,
+          _ => break,
+        };
+
+        call.args.insert(slot, previous);
+        self.folded.insert(previous);
+        cursor = previous;
+      }
+
+      self.calls.insert(address, call);
+    }
+
+    for function in self.functions.iter_mut() {
+      let args = self
+        .calls
+        .values()
+        .filter(|call| call.target == function.entry)
+        .filter_map(|call| call.args.keys().next_back().copied())
+        .max()
+        .unwrap_or(0);
+      function.params = args.min((function.frame - 1).max(0));
+    }
+  }
+
+  // Hides each function's prologue and the `arb` undoing it before every
+  // return.
+  fn fold_frames(&mut self) {
+    for function in self.functions.iter() {
+      if function.frame == 0 {
+        continue;
+      }
+      self.folded.insert(function.entry);
+
+      for &address in function.body.iter() {
+        if self.exits.get(&address) != Some(&Exit::Return) {
+          continue;
+        }
+
+        if let Some((&previous, instruction)) = self.instructions.range(..address).next_back() {
+          if instruction.opcode == Opcode::AdjustRelativeBase
+            && instruction.params[0] == Parameter::Immediate(-function.frame)
+            && previous + 2 == address
+          {
+            self.folded.insert(previous);
+          }
+        }
+      }
+    }
+  }
+
+  // Turns `lt a, b -> [flag]` followed by `jt [flag], ...` into a jump on
+  // `a < b`.
+  fn fold_conditions(&mut self) {
+    let mut flags = Vec::new();
+
+    for function in self.functions.iter() {
+      for &address in function.body.iter() {
+        let instruction = &self.instructions[&address];
+        if !instruction.opcode.is_jump() {
+          continue;
+        }
+
+        let mut condition = Condition {
+          left: self.operand(function, address, 0, instruction.params[0]),
+          op: "!=",
+          right: String::from("0"),
+        };
+
+        if let Some((&previous, flag)) = self.instructions.range(..address).next_back() {
+          let tests_flag = previous + 4 == address
+            && matches!(flag.opcode, Opcode::LessThan | Opcode::Equals)
+            && flag.params[2] == instruction.params[0]
+            && !self.folded.contains(&previous)
+            && !self.modified.contains(&(address + 1));
+
+          if tests_flag {
+            condition = Condition {
+              left: self.operand(function, previous, 0, flag.params[0]),
+              op: if flag.opcode == Opcode::LessThan {
+                "<"
+              } else {
+                "=="
+              },
+              right: self.operand(function, previous, 1, flag.params[1]),
+            };
+            flags.push(previous);
+          }
+        }
+
+        if instruction.opcode == Opcode::JumpIfFalse {
+          condition = condition.negate();
+        }
+        self.conditions.insert(address, condition);
+      }
+    }
+
+    self.folded.extend(flags);
+  }
+
+  // Names a relative slot from the point of view of `function`'s frame.
+  fn slot(&self, function: &Function, offset: i64) -> String {
+    let slot = offset + function.frame;
+
+    if offset > 0 {
+      format!("out{}", offset)
+    } else if offset == 0 {
+      String::from("mem[rb]")
+    } else if slot == 0 {
+      String::from("ret")
+    } else if slot > 0 && slot <= function.params {
+      format!("arg{}", slot)
+    } else if slot > 0 {
+      format!("local{}", slot - function.params)
+    } else {
+      format!("mem[rb{}]", offset)
+    }
+  }
+
+  // Renders operand `index` of the instruction at `address`. Operands other
+  // code writes to are read from memory at runtime.
+  fn operand(&self, function: &Function, address: u64, index: usize, param: Parameter) -> String {
+    let word = address + 1 + index as u64;
+
+    if self.modified.contains(&word) {
+      return match param {
+        Parameter::Position(_) => format!("mem[g{}]", word),
+        Parameter::Immediate(_) => format!("g{}", word),
+        Parameter::Relative(_) => format!("mem[rb + g{}]", word),
+      };
+    }
+
+    match param {
+      Parameter::Position(address) if address >= 0 => format!("g{}", address),
+      Parameter::Position(address) => format!("mem[{}]", address),
+      Parameter::Immediate(value) => value.to_string(),
+      Parameter::Relative(offset) => self.slot(function, offset),
+    }
+  }
+
+  // The value an add, multiply, compare or input instruction stores.
+  fn value(&self, function: &Function, address: u64, instruction: &Instruction) -> String {
+    if let Some(value) = constant_result(instruction) {
+      if !self.modified.contains(&(address + 1)) && !self.modified.contains(&(address + 2)) {
+        return value.to_string();
+      }
+    }
+
+    let operand = |index: usize| self.operand(function, address, index, instruction.params[index]);
+
+    match instruction.opcode {
+      Opcode::Add => match (operand(0).as_str(), operand(1).as_str()) {
+        ("0", b) => b.to_string(),
+        (a, "0") => a.to_string(),
+        (a, b) if b.starts_with('-') => format!("{} - {}", a, &b[1..]),
+        (a, b) => format!("{} + {}", a, b),
+      },
+      Opcode::Multiply => match (operand(0).as_str(), operand(1).as_str()) {
+        ("0", _) | (_, "0") => String::from("0"),
+        ("1", b) => b.to_string(),
+        (a, "1") => a.to_string(),
+        ("-1", b) => format!("-{}", b),
+        (a, "-1") => format!("-{}", a),
+        (a, b) => format!("{} * {}", a, b),
+      },
+      Opcode::LessThan => format!("{} < {}", operand(0), operand(1)),
+      Opcode::Equals => format!("{} == {}", operand(0), operand(1)),
+      Opcode::Input => String::from("input()"),
+      _ => String::new(),
+    }
+  }
+
+  fn render(&self) -> String {
+    let mut text = String::new();
+
+    for function in self.functions.iter() {
+      // the first pass finds which jumps stay gotos, the second one labels
+      // their targets
+      let mut emitter = Emitter {
+        decompiler: self,
+        function,
+        lines: Vec::new(),
+        gotos: BTreeSet::new(),
+        labels: BTreeSet::new(),
+      };
+      emitter.emit(0, function.body.len(), 1);
+
+      let labels = emitter.gotos.clone();
+      let mut emitter = Emitter {
+        lines: Vec::new(),
+        gotos: BTreeSet::new(),
+        labels,
+        ..emitter
+      };
+      emitter.emit(0, function.body.len(), 1);
+
+      let params: Vec<String> = (1..=function.params)
+        .map(|slot| format!("arg{}", slot))
+        .collect();
+      if function.entry == 0 {
+        writeln!(text, "fn main() {{").unwrap();
+      } else {
+        writeln!(text, "fn f{}({}) {{", function.entry, params.join(", ")).unwrap();
+      }
+
+      let locals: Vec<String> = (1..function.frame - function.params)
+        .map(|local| format!("local{}", local))
+        .collect();
+      if !locals.is_empty() {
+        writeln!(text, "  let {};", locals.join(", ")).unwrap();
+      }
+
+      for line in emitter.lines.iter() {
+        writeln!(text, "{}", line).unwrap();
+      }
+      writeln!(text, "}}\n").unwrap();
+    }
+
+    text.pop();
+    text
+  }
+}
+
+struct Emitter<'a> {
+  decompiler: &'a Decompiler,
+  function: &'a Function,
+  lines: Vec<String>,
+  gotos: BTreeSet<u64>,
+  labels: BTreeSet<u64>,
+}
+
+impl<'a> Emitter<'a> {
+  fn line(&mut self, depth: usize, text: String) {
+    self.lines.push(format!("{}{}", "  ".repeat(depth), text));
+  }
+
+  fn index_of(&self, address: u64) -> Option<usize> {
+    self.function.body.binary_search(&address).ok()
+  }
+
+  fn is_call(&self, address: u64) -> bool {
+    self.decompiler.calls.contains_key(&address)
+  }
+
+  // An unconditional jump to an immediate target that isn't a call.
+  fn goto_target(&self, address: u64) -> Option<u64> {
+    let instruction = &self.decompiler.instructions[&address];
+    if self.is_call(address) {
+      return None;
+    }
+
+    let target = known_target(instruction)?;
+    match instruction.params[0] {
+      Parameter::Immediate(value) if (value != 0) == (instruction.opcode == Opcode::JumpIfTrue) => {
+        Some(target)
+      }
+      _ => None,
+    }
+  }
+
+  fn is_conditional(&self, address: u64) -> bool {
+    let instruction = &self.decompiler.instructions[&address];
+    instruction.opcode.is_jump()
+      && !matches!(instruction.params[0], Parameter::Immediate(_))
+      && known_target(instruction).is_some()
+  }
+
+  // Emits the instructions body[start..end], structuring the jumps that stay
+  // inside that range.
+  fn emit(&mut self, start: usize, end: usize, depth: usize) {
+    let body = &self.function.body;
+    let mut index = start;
+
+    while index < end {
+      let address = body[index];
+
+      if self.labels.contains(&address) {
+        self.line(depth.saturating_sub(1), format!("L{}:", address));
+      }
+
+      // the last backward jump to here closes a loop
+      let back = (index + 1..end).rev().find(|&other| {
+        let other = body[other];
+        !self.is_call(other)
+          && self.decompiler.instructions[&other].jump_target() == Some(address as i64)
+          && (self.is_conditional(other) || self.goto_target(other).is_some())
+      });
+
+      if let Some(back) = back {
+        let jump = body[back];
+        if self.is_conditional(jump) {
+          self.line(depth, String::from("do {"));
+          self.emit(index, back, depth + 1);
+          let condition = self.decompiler.conditions[&jump].render();
+          self.line(depth, format!("}} while ({});", condition));
+        } else {
+          self.line(depth, String::from("loop {"));
+          self.emit(index, back, depth + 1);
+          self.line(depth, String::from("}"));
+        }
+        index = back + 1;
+        continue;
+      }
+
+      if self.decompiler.folded.contains(&address) {
+        index += 1;
+        continue;
+      }
+
+      if self.is_conditional(address) {
+        let target = known_target(&self.decompiler.instructions[&address]).unwrap();
+
+        match self.index_of(target) {
+          Some(then_end) if then_end > index && then_end <= end => {
+            let condition = self.decompiler.conditions[&address].negate().render();
+            self.line(depth, format!("if ({}) {{", condition));
+
+            // a forward jump at the end of the block skips an else block
+            let otherwise = then_end
+              .checked_sub(1)
+              .filter(|&last| last > index)
+              .and_then(|last| self.goto_target(body[last]).map(|target| (last, target)))
+              .and_then(|(last, target)| Some((last, self.index_of(target)?)))
+              .filter(|&(_, else_end)| else_end > then_end && else_end <= end);
+
+            match otherwise {
+              Some((last, else_end)) => {
+                self.emit(index + 1, last, depth + 1);
+                self.line(depth, String::from("} else {"));
+                self.emit(then_end, else_end, depth + 1);
+                self.line(depth, String::from("}"));
+                index = else_end;
+              }
+              None => {
+                self.emit(index + 1, then_end, depth + 1);
+                self.line(depth, String::from("}"));
+                index = then_end;
+              }
+            }
+            continue;
+          }
+          _ => {
+            let condition = self.decompiler.conditions[&address].render();
+            self.gotos.insert(target);
+            self.line(depth, format!("if ({}) goto L{};", condition, target));
+          }
+        }
+      } else {
+        let statement = self.statement(address);
+        self.line(depth, statement);
+      }
+
+      index += 1;
+    }
+  }
+
+  fn statement(&mut self, address: u64) -> String {
+    let decompiler = self.decompiler;
+    let function = self.function;
+    let instruction = &decompiler.instructions[&address];
+    let operand =
+      |index: usize| decompiler.operand(function, address, index, instruction.params[index]);
+
+    if let Some(call) = decompiler.calls.get(&address) {
+      let count = call.args.keys().next_back().copied().unwrap_or(0);
+      let args: Vec<String> = (1..=count)
+        .map(|slot| match call.args.get(&slot) {
+          Some(&store) => decompiler.value(function, store, &decompiler.instructions[&store]),
+          None => String::from("_"),
+        })
+        .collect();
+      return format!("f{}({});", call.target, args.join(", "));
+    }
+
+    match instruction.opcode {
+      Opcode::Add | Opcode::Multiply | Opcode::LessThan | Opcode::Equals | Opcode::Input => {
+        let index = instruction.opcode.write_parameter().unwrap();
+        format!(
+          "{} = {};",
+          operand(index),
+          decompiler.value(function, address, instruction)
+        )
+      }
+      Opcode::Output => format!("output({});", operand(0)),
+      Opcode::AdjustRelativeBase => match operand(0) {
+        value if value.starts_with('-') => format!("rb -= {};", &value[1..]),
+        value => format!("rb += {};", value),
+      },
+      Opcode::Halt => String::from("halt();"),
+      Opcode::JumpIfTrue | Opcode::JumpIfFalse => {
+        match (decompiler.exits.get(&address), self.goto_target(address)) {
+          (Some(Exit::Return), _) => String::from("return;"),
+          (_, Some(target)) => {
+            self.gotos.insert(target);
+            format!("goto L{};", target)
+          }
+          _ if matches!(instruction.params[0], Parameter::Immediate(_)) => {
+            format!("goto *{};", operand(1))
+          }
+          _ => {
+            let condition = decompiler.conditions[&address].render();
+            format!("if ({}) goto *{};", condition, operand(1))
+          }
+        }
+      }
+    }
+  }
+}
+
+// The immediate target of a jump. A negative one faults when taken, so like
+// one only known at runtime it gets no label.
+fn known_target(instruction: &Instruction) -> Option<u64> {
+  match instruction.jump_target() {
+    Some(target) if target >= 0 => Some(target as u64),
+    _ => None,
+  }
+}
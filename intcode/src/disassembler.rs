@@ -0,0 +1,196 @@
+use crate::instruction::{Instruction, Parameter};
+use std::collections::BTreeSet;
+use std::fmt;
+use std::fmt::Write;
+
+const DATA_PER_LINE: usize = 8;
+const COMMENT_COLUMN: usize = 40;
+
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub enum Line {
+  Instruction {
+    address: u64,
+    instruction: Instruction,
+  },
+  Data {
+    address: u64,
+    values: Vec<i64>,
+  },
+}
+
+impl Line {
+  pub fn address(&self) -> u64 {
+    match *self {
+      Line::Instruction { address, .. } | Line::Data { address, .. } => address,
+    }
+  }
+}
+
+#[derive(Debug, Clone)]
+pub struct Listing {
+  pub lines: Vec<Line>,
+  pub labels: BTreeSet<u64>,
+}
+
+pub fn label_name(address: u64) -> String {
+  format!("L{}", address)
+}
+
+// Disassembles a program with a linear sweep from address 0. Words that
+// don't decode cleanly, or instructions that would swallow the target of a
+// jump, are emitted as data instead. Sweeping again after splitting
+// instructions around labels may reveal new jumps, so this repeats until the
+// set of labels settles.
+pub fn disassemble(program: &[i64]) -> Listing {
+  let mut labels = BTreeSet::new();
+
+  loop {
+    let lines = sweep(program, &labels);
+    let targets = jump_targets(program, &lines);
+
+    if targets.is_subset(&labels) {
+      return Listing { lines, labels };
+    }
+
+    labels.extend(targets);
+  }
+}
+
+fn sweep(program: &[i64], labels: &BTreeSet<u64>) -> Vec<Line> {
+  let mut lines: Vec<Line> = Vec::new();
+  let mut addr = 0;
+
+  while addr < program.len() {
+    let instruction = Instruction::decode(&program[addr..]).filter(|instruction| {
+      let inner = (addr as u64 + 1)..((addr + instruction.size()) as u64);
+      labels.range(inner).next().is_none()
+    });
+
+    match instruction {
+      Some(instruction) => {
+        let size = instruction.size();
+        lines.push(Line::Instruction {
+          address: addr as u64,
+          instruction,
+        });
+        addr += size;
+      }
+      None => {
+        let value = program[addr];
+
+        match lines.last_mut() {
+          Some(Line::Data { values, .. })
+            if values.len() < DATA_PER_LINE && !labels.contains(&(addr as u64)) =>
+          {
+            values.push(value);
+          }
+          _ => lines.push(Line::Data {
+            address: addr as u64,
+            values: vec![value],
+          }),
+        }
+
+        addr += 1;
+      }
+    }
+  }
+
+  lines
+}
+
+// Immediate targets of opcodes 5/6 that land inside the program.
+fn jump_targets(program: &[i64], lines: &[Line]) -> BTreeSet<u64> {
+  lines
+    .iter()
+    .filter_map(|line| match line {
+      Line::Instruction { instruction, .. } => instruction.jump_target(),
+      Line::Data { .. } => None,
+    })
+    .filter(|&target| target >= 0 && (target as usize) < program.len())
+    .map(|target| target as u64)
+    .collect()
+}
+
+impl Listing {
+  fn format_line(&self, line: &Line) -> Result<String, fmt::Error> {
+    let mut text = String::from("  ");
+
+    match line {
+      Line::Instruction { instruction, .. } => {
+        instruction.format_with(&mut text, |index, param| match param {
+          Parameter::Immediate(target)
+            if index == 1
+              && instruction.opcode.is_jump()
+              && target >= 0
+              && self.labels.contains(&(target as u64)) =>
+          {
+            format!("#{}", label_name(target as u64))
+          }
+          _ => param.to_string(),
+        })?;
+      }
+      Line::Data { values, .. } => {
+        let values: Vec<String> = values.iter().map(|v| v.to_string()).collect();
+        write!(text, "data {}", values.join(", "))?;
+      }
+    }
+
+    Ok(text)
+  }
+
+  fn raw_words(line: &Line) -> Vec<i64> {
+    match line {
+      Line::Instruction { instruction, .. } => instruction.encode(),
+      Line::Data { values, .. } => values.clone(),
+    }
+  }
+
+  // Writes the listing with a margin in front of every line, filled in by
+  // `margin`. Label lines get a blank margin of the same width.
+  pub fn write_with_margin<F>(
+    &self,
+    f: &mut dyn fmt::Write,
+    width: usize,
+    mut margin: F,
+  ) -> fmt::Result
+  where
+    F: FnMut(&Line) -> String,
+  {
+    for line in self.lines.iter() {
+      if self.labels.contains(&line.address()) {
+        writeln!(
+          f,
+          "{:width$}{}:",
+          "",
+          label_name(line.address()),
+          width = width
+        )?;
+      }
+
+      let text = self.format_line(line)?;
+      let words: Vec<String> = Listing::raw_words(line)
+        .iter()
+        .map(|w| w.to_string())
+        .collect();
+
+      writeln!(
+        f,
+        "{:margin_width$}{:width$}; {}: {}",
+        margin(line),
+        text,
+        line.address(),
+        words.join(","),
+        margin_width = width,
+        width = COMMENT_COLUMN
+      )?;
+    }
+
+    Ok(())
+  }
+}
+
+impl fmt::Display for Listing {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    self.write_with_margin(f, 0, |_| String::new())
+  }
+}
@@ -0,0 +1,110 @@
+use std::fmt;
+
+// A fault is raised instead of panicking whenever the program asks the
+// computer to do something it can't. Every variant records where the
+// machine was (pc, fp) and the raw instruction it was executing.
+#[derive(Debug, PartialEq, Eq, Copy, Clone)]
+pub enum Fault {
+  UnknownOpcode {
+    pc: u64,
+    fp: i64,
+    instruction: i64,
+  },
+  InvalidParameterMode {
+    pc: u64,
+    fp: i64,
+    instruction: i64,
+    mode: i64,
+  },
+  NegativeAddress {
+    pc: u64,
+    fp: i64,
+    instruction: i64,
+    address: i64,
+  },
+  WriteInImmediateMode {
+    pc: u64,
+    fp: i64,
+    instruction: i64,
+  },
+  AddressOutOfRange {
+    pc: u64,
+    fp: i64,
+    instruction: i64,
+    address: u64,
+  },
+  // only raised in checked arithmetic mode, or when the relative base
+  // leaves the range of an i64
+  Overflow {
+    pc: u64,
+    fp: i64,
+    instruction: i64,
+  },
+  // a plug-in asked to store a value but has no write parameter to store
+  // it to
+  NoWriteParameter {
+    pc: u64,
+    fp: i64,
+    instruction: i64,
+  },
+}
+
+impl Fault {
+  pub fn pc(&self) -> u64 {
+    match *self {
+      Fault::UnknownOpcode { pc, .. }
+      | Fault::InvalidParameterMode { pc, .. }
+      | Fault::NegativeAddress { pc, .. }
+      | Fault::WriteInImmediateMode { pc, .. }
+      | Fault::AddressOutOfRange { pc, .. }
+      | Fault::Overflow { pc, .. }
+      | Fault::NoWriteParameter { pc, .. } => pc,
+    }
+  }
+
+  pub fn fp(&self) -> i64 {
+    match *self {
+      Fault::UnknownOpcode { fp, .. }
+      | Fault::InvalidParameterMode { fp, .. }
+      | Fault::NegativeAddress { fp, .. }
+      | Fault::WriteInImmediateMode { fp, .. }
+      | Fault::AddressOutOfRange { fp, .. }
+      | Fault::Overflow { fp, .. }
+      | Fault::NoWriteParameter { fp, .. } => fp,
+    }
+  }
+
+  pub fn instruction(&self) -> i64 {
+    match *self {
+      Fault::UnknownOpcode { instruction, .. }
+      | Fault::InvalidParameterMode { instruction, .. }
+      | Fault::NegativeAddress { instruction, .. }
+      | Fault::WriteInImmediateMode { instruction, .. }
+      | Fault::AddressOutOfRange { instruction, .. }
+      | Fault::Overflow { instruction, .. }
+      | Fault::NoWriteParameter { instruction, .. } => instruction,
+    }
+  }
+}
+
+impl fmt::Display for Fault {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    match *self {
+      Fault::UnknownOpcode { instruction, .. } => write!(f, "unknown opcode {}", instruction % 100),
+      Fault::InvalidParameterMode { mode, .. } => write!(f, "invalid parameter mode {}", mode),
+      Fault::NegativeAddress { address, .. } => write!(f, "negative address {}", address),
+      Fault::WriteInImmediateMode { .. } => write!(f, "write parameter in immediate mode"),
+      Fault::AddressOutOfRange { address, .. } => write!(f, "address {} out of range", address),
+      Fault::Overflow { .. } => write!(f, "arithmetic overflow"),
+      Fault::NoWriteParameter { .. } => write!(f, "store without a write parameter"),
+    }?;
+
+    write!(
+      f,
+      " (pc: {}, fp: {}, instruction: {})",
+      self.pc(),
+      self.fp(),
+      self.instruction()
+    )
+  }
+}
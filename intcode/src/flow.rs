@@ -0,0 +1,374 @@
+use crate::disassembler::label_name;
+use crate::instruction::{Instruction, Opcode, Parameter};
+use std::collections::{BTreeMap, BTreeSet};
+use std::fmt::Write;
+
+#[derive(Debug, PartialEq, Eq, Copy, Clone)]
+pub enum EdgeKind {
+  // the block runs straight into the next one
+  Next,
+  // a jump that is taken, and a conditional jump that isn't
+  Taken,
+  NotTaken,
+  // a call, and where it returns to
+  Call,
+  AfterCall,
+}
+
+#[derive(Debug, PartialEq, Eq, Copy, Clone)]
+pub struct Edge {
+  pub from: u64,
+  pub to: u64,
+  pub kind: EdgeKind,
+}
+
+// How a basic block ends.
+#[derive(Debug, PartialEq, Eq, Copy, Clone)]
+pub enum Exit {
+  // runs into the start of another block
+  Next,
+  // a jump to an immediate target, conditional or not
+  Jump,
+  // a jump whose target is only known at runtime
+  Indirect,
+  // a call: the return address stored in [rb+0], then a jump
+  Call,
+  // a jump back to the address in [rb+0]
+  Return,
+  Halt,
+  // runs into words that don't decode, or off the end of the program
+  Invalid,
+}
+
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct Block {
+  pub start: u64,
+  pub instructions: Vec<(u64, Instruction)>,
+  pub exit: Exit,
+}
+
+impl Block {
+  // One past the last word of the block.
+  pub fn end(&self) -> u64 {
+    match self.instructions.last() {
+      Some((address, instruction)) => address + instruction.size() as u64,
+      None => self.start,
+    }
+  }
+}
+
+// A reachable instruction whose words are overwritten by another reachable
+// instruction. Only writes to fixed (position mode) addresses are seen;
+// relative writes depend on the relative base at runtime.
+#[derive(Debug, PartialEq, Eq, Copy, Clone)]
+pub struct Modification {
+  pub writer: u64,
+  pub instruction: u64,
+  pub address: u64,
+}
+
+#[derive(Debug, Clone)]
+pub struct FlowGraph {
+  pub blocks: BTreeMap<u64, Block>,
+  pub edges: Vec<Edge>,
+  pub modifications: Vec<Modification>,
+}
+
+// Where control can go after one instruction.
+struct Step {
+  successors: Vec<(u64, EdgeKind)>,
+  exit: Option<Exit>,
+}
+
+// Builds the control-flow graph of everything reachable from address 0,
+// following fall-through and jumps to immediate targets. Jumps whose
+// condition is an immediate only get the edge that can actually happen, so
+// the usual `jt #1, #target` is an unconditional jump. Calls are recognised
+// the way compiled AoC programs make them: the address right after the jump
+// is stored in [rb+0] just before jumping.
+pub fn analyze(program: &[i64]) -> FlowGraph {
+  analyze_from(program, &[0])
+}
+
+// Like `analyze`, but following control from each of `entries`.
+pub(crate) fn analyze_from(program: &[i64], entries: &[u64]) -> FlowGraph {
+  let mut steps: BTreeMap<u64, (Option<Instruction>, Step)> = BTreeMap::new();
+  let mut leaders: BTreeSet<u64> = entries.iter().cloned().collect();
+  let mut pending = entries.to_vec();
+
+  while let Some(address) = pending.pop() {
+    if steps.contains_key(&address) {
+      continue;
+    }
+
+    let instruction = program
+      .get(address as usize..)
+      .and_then(Instruction::decode);
+    let step = step(program, address, instruction.as_ref());
+
+    for &(to, kind) in step.successors.iter() {
+      if kind != EdgeKind::Next {
+        leaders.insert(to);
+      }
+      pending.push(to);
+    }
+
+    steps.insert(address, (instruction, step));
+  }
+
+  let mut blocks = BTreeMap::new();
+  let mut edges = Vec::new();
+
+  for &start in leaders.iter() {
+    let mut block = Block {
+      start,
+      instructions: Vec::new(),
+      exit: Exit::Invalid,
+    };
+    let mut address = start;
+
+    loop {
+      let (instruction, step) = &steps[&address];
+      let instruction = match instruction {
+        Some(instruction) => instruction,
+        None => break,
+      };
+      block.instructions.push((address, instruction.clone()));
+
+      if let Some(exit) = step.exit {
+        block.exit = exit;
+        edges.extend(step.successors.iter().map(|&(to, kind)| Edge {
+          from: start,
+          to,
+          kind,
+        }));
+        break;
+      }
+
+      address += instruction.size() as u64;
+      if leaders.contains(&address) {
+        block.exit = Exit::Next;
+        edges.push(Edge {
+          from: start,
+          to: address,
+          kind: EdgeKind::Next,
+        });
+        break;
+      }
+    }
+
+    blocks.insert(start, block);
+  }
+
+  let modifications = modifications(&steps);
+
+  FlowGraph {
+    blocks,
+    edges,
+    modifications,
+  }
+}
+
+fn step(program: &[i64], address: u64, instruction: Option<&Instruction>) -> Step {
+  let instruction = match instruction {
+    Some(instruction) => instruction,
+    None => {
+      return Step {
+        successors: Vec::new(),
+        exit: Some(Exit::Invalid),
+      }
+    }
+  };
+
+  let next = address + instruction.size() as u64;
+
+  match instruction.opcode {
+    Opcode::Halt => Step {
+      successors: Vec::new(),
+      exit: Some(Exit::Halt),
+    },
+    Opcode::JumpIfTrue | Opcode::JumpIfFalse => {
+      let when = instruction.opcode == Opcode::JumpIfTrue;
+      let (can_jump, can_continue) = match instruction.params[0] {
+        Parameter::Immediate(value) => ((value != 0) == when, (value != 0) != when),
+        _ => (true, true),
+      };
+
+      let mut successors = Vec::new();
+      let mut exit = Exit::Jump;
+
+      if can_jump {
+        match instruction.jump_target() {
+          Some(target) if target >= 0 && !can_continue && is_call(program, address, next) => {
+            return Step {
+              successors: vec![(target as u64, EdgeKind::Call), (next, EdgeKind::AfterCall)],
+              exit: Some(Exit::Call),
+            };
+          }
+          Some(target) if target >= 0 => successors.push((target as u64, EdgeKind::Taken)),
+          Some(_) => (),
+          None if !can_continue && instruction.params[1] == Parameter::Relative(0) => {
+            exit = Exit::Return
+          }
+          None => exit = Exit::Indirect,
+        }
+      }
+      if can_continue {
+        successors.push((next, EdgeKind::NotTaken));
+      }
+
+      Step {
+        successors,
+        exit: Some(exit),
+      }
+    }
+    _ => Step {
+      successors: vec![(next, EdgeKind::Next)],
+      exit: None,
+    },
+  }
+}
+
+// Whether the jump at `address` is preceded by an instruction storing the
+// return address `next` in [rb+0].
+fn is_call(program: &[i64], address: u64, next: u64) -> bool {
+  let store = match address.checked_sub(4) {
+    Some(store) => store,
+    None => return false,
+  };
+
+  match program.get(store as usize..).and_then(Instruction::decode) {
+    Some(instruction) => {
+      instruction.params.get(2) == Some(&Parameter::Relative(0))
+        && constant_result(&instruction) == Some(next as i64)
+    }
+    None => false,
+  }
+}
+
+// The value an add or multiply of two immediates stores.
+pub(crate) fn constant_result(instruction: &Instruction) -> Option<i64> {
+  match (instruction.opcode, &instruction.params[..]) {
+    (Opcode::Add, [Parameter::Immediate(a), Parameter::Immediate(b), _]) => a.checked_add(*b),
+    (Opcode::Multiply, [Parameter::Immediate(a), Parameter::Immediate(b), _]) => a.checked_mul(*b),
+    _ => None,
+  }
+}
+
+fn modifications(steps: &BTreeMap<u64, (Option<Instruction>, Step)>) -> Vec<Modification> {
+  // every word of every reachable instruction, and the instruction it's in
+  let mut owners = BTreeMap::new();
+  for (&address, (instruction, _)) in steps.iter() {
+    if let Some(instruction) = instruction {
+      for word in address..address + instruction.size() as u64 {
+        owners.entry(word).or_insert_with(Vec::new).push(address);
+      }
+    }
+  }
+
+  let mut modifications = Vec::new();
+  for (&writer, (instruction, _)) in steps.iter() {
+    let instruction = match instruction {
+      Some(instruction) => instruction,
+      None => continue,
+    };
+
+    let target = match instruction.opcode.write_parameter() {
+      Some(index) => match instruction.params[index] {
+        Parameter::Position(target) if target >= 0 => target as u64,
+        _ => continue,
+      },
+      None => continue,
+    };
+
+    if let Some(owners) = owners.get(&target) {
+      modifications.extend(owners.iter().map(|&owner| Modification {
+        writer,
+        instruction: owner,
+        address: target,
+      }));
+    }
+  }
+
+  modifications
+}
+
+impl FlowGraph {
+  // The block containing the instruction at `address`, if it's reachable.
+  pub fn block_at(&self, address: u64) -> Option<&Block> {
+    self.blocks.values().find(|block| {
+      block.instructions.iter().any(|&(start, ref instruction)| {
+        (start..start + instruction.size() as u64).contains(&address)
+      })
+    })
+  }
+
+  pub fn is_self_modifying(&self) -> bool {
+    !self.modifications.is_empty()
+  }
+
+  // The graph in Graphviz format, one box per block. Instructions that get
+  // overwritten are marked, and their blocks drawn in red.
+  pub fn to_dot(&self) -> String {
+    let mut dot = String::from("digraph intcode {\n  node [shape=box, fontname=\"monospace\"];\n");
+
+    for block in self.blocks.values() {
+      let mut label = format!("{}:\\l", label_name(block.start));
+      let mut modified = false;
+
+      for (address, instruction) in block.instructions.iter() {
+        let writers: Vec<String> = self
+          .modifications
+          .iter()
+          .filter(|modification| modification.instruction == *address)
+          .map(|modification| modification.writer.to_string())
+          .collect();
+
+        write!(label, "{:>6}: {}", address, instruction).unwrap();
+        if !writers.is_empty() {
+          modified = true;
+          write!(label, "  ; modified by {}", writers.join(", ")).unwrap();
+        }
+        label.push_str("\\l");
+      }
+
+      match block.exit {
+        Exit::Indirect => label.push_str("(indirect jump)\\l"),
+        Exit::Return => label.push_str("(return)\\l"),
+        Exit::Invalid => write!(label, "(invalid at {})\\l", block.end()).unwrap(),
+        _ => (),
+      }
+
+      let color = if modified { ", color=red" } else { "" };
+      writeln!(
+        dot,
+        "  {} [label=\"{}\"{}];",
+        label_name(block.start),
+        label,
+        color
+      )
+      .unwrap();
+    }
+
+    for edge in self.edges.iter() {
+      let style = match edge.kind {
+        EdgeKind::Next => "",
+        EdgeKind::Taken => " [label=\"taken\"]",
+        EdgeKind::NotTaken => " [label=\"not taken\", style=dashed]",
+        EdgeKind::Call => " [label=\"call\", style=bold]",
+        EdgeKind::AfterCall => " [label=\"returns\", style=dotted]",
+      };
+      writeln!(
+        dot,
+        "  {} -> {}{};",
+        label_name(edge.from),
+        label_name(edge.to),
+        style
+      )
+      .unwrap();
+    }
+
+    dot.push_str("}\n");
+    dot
+  }
+}
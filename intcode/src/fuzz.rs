@@ -0,0 +1,383 @@
+use crate::computer::{Computer, Engine, Interruption};
+use crate::instruction::{Instruction, Opcode, Parameter};
+use crate::snapshot::Snapshot;
+use std::fmt;
+
+// Instructions a single run may execute, so generated loops end.
+const BUDGET: u64 = 2_000;
+// Generated operands address this many words, so programs read and write
+// over their own code and data.
+const SPAN: i64 = 64;
+const MAX_INSTRUCTIONS: u64 = 16;
+const MAX_INPUTS: u64 = 6;
+
+// A program and the inputs fed to it, one each time it asks.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct Case {
+  pub program: Vec<i64>,
+  pub inputs: Vec<i64>,
+}
+
+// What a run did: how it ended, what it output, and the address and value
+// of every non-zero word left in its memory.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct Run {
+  pub ending: String,
+  pub outputs: Vec<i64>,
+  pub memory: Vec<(u64, i64)>,
+}
+
+pub type Implementation = Box<dyn Fn(&Case) -> Run>;
+
+// A case the implementations don't agree on, and what each of them did.
+#[derive(Debug, Clone)]
+pub struct Discrepancy {
+  pub case: Case,
+  pub runs: Vec<(String, Run)>,
+}
+
+// Generates random well-formed programs, runs each on every implementation
+// and reports the ones they disagree on, shrunk to as small a program as
+// still shows the disagreement.
+pub struct Fuzzer {
+  implementations: Vec<(String, Implementation)>,
+  state: u64,
+}
+
+impl Fuzzer {
+  // A fuzzer comparing every way this crate can run an i64 program: both
+  // engines, single steps, short `run_for` slices, and a snapshot round trip
+  // between slices. Wider words aren't compared, since they rightly
+  // disagree with i64 whenever arithmetic overflows.
+  pub fn new(seed: u64) -> Fuzzer {
+    let mut fuzzer = Fuzzer {
+      implementations: Vec::new(),
+      // xorshift gets stuck at zero
+      state: seed.max(1),
+    };
+
+    fuzzer.add_implementation(
+      "interpreter",
+      Box::new(|case| {
+        drive(case, Engine::Interpreter, |computer, budget| {
+          computer.run_for(budget)
+        })
+      }),
+    );
+    fuzzer.add_implementation(
+      "cached",
+      Box::new(|case| {
+        drive(case, Engine::Cached, |computer, budget| {
+          computer.run_for(budget)
+        })
+      }),
+    );
+    fuzzer.add_implementation(
+      "stepped",
+      Box::new(|case| {
+        drive(case, Engine::Interpreter, |computer, budget| {
+          for _ in 0..budget {
+            if let Some(interruption) = computer.step() {
+              return interruption;
+            }
+          }
+          Interruption::BudgetExhausted
+        })
+      }),
+    );
+    fuzzer.add_implementation(
+      "sliced",
+      Box::new(|case| {
+        drive(case, Engine::Cached, |computer, budget| {
+          computer.run_for(budget.min(3))
+        })
+      }),
+    );
+    fuzzer.add_implementation(
+      "snapshot",
+      Box::new(|case| {
+        drive(case, Engine::Interpreter, |computer, budget| {
+          let text = computer.snapshot().encode();
+          *computer =
+            Computer::from_snapshot(Snapshot::decode(&text).expect("Failed to decode snapshot"));
+          computer.run_for(budget.min(5))
+        })
+      }),
+    );
+
+    fuzzer
+  }
+
+  pub fn add_implementation(&mut self, name: &str, implementation: Implementation) {
+    self
+      .implementations
+      .push((name.to_string(), implementation));
+  }
+
+  // Runs `iterations` random cases, returning the minimized ones the
+  // implementations disagree on.
+  pub fn fuzz(&mut self, iterations: usize) -> Vec<Discrepancy> {
+    let mut found: Vec<Discrepancy> = Vec::new();
+
+    for _ in 0..iterations {
+      let case = self.generate();
+      if self.check(&case).is_none() {
+        continue;
+      }
+
+      let case = self.minimize(case);
+      if found.iter().all(|discrepancy| discrepancy.case != case) {
+        found.extend(self.check(&case));
+      }
+    }
+
+    found
+  }
+
+  // A random program of a few instructions ending in a halt, with valid
+  // opcodes and modes, and a few inputs for it. Jumps mostly land on the
+  // start of an instruction.
+  pub fn generate(&mut self) -> Case {
+    let count = 1 + self.below(MAX_INSTRUCTIONS);
+    let mut instructions = Vec::new();
+
+    for _ in 0..count {
+      let opcode = Opcode::all()[self.below(10) as usize];
+      let params = (0..opcode.arity())
+        .map(|index| self.parameter(opcode.write_parameter() == Some(index)))
+        .collect();
+      instructions.push(Instruction { opcode, params });
+    }
+    instructions.push(Instruction {
+      opcode: Opcode::Halt,
+      params: Vec::new(),
+    });
+
+    let mut starts = Vec::new();
+    let mut address = 0;
+    for instruction in instructions.iter() {
+      starts.push(address);
+      address += instruction.size() as i64;
+    }
+
+    for instruction in instructions.iter_mut() {
+      if instruction.opcode.is_jump() && self.below(4) != 0 {
+        let target = starts[self.below(starts.len() as u64) as usize];
+        instruction.params[1] = Parameter::Immediate(target);
+      }
+    }
+
+    let inputs = (0..self.below(MAX_INPUTS + 1))
+      .map(|_| self.between(-3, 10))
+      .collect();
+
+    Case {
+      program: instructions.iter().flat_map(|i| i.encode()).collect(),
+      inputs,
+    }
+  }
+
+  // Runs the case on every implementation, returning what each did.
+  pub fn run(&self, case: &Case) -> Vec<(String, Run)> {
+    self
+      .implementations
+      .iter()
+      .map(|(name, implementation)| (name.clone(), implementation(case)))
+      .collect()
+  }
+
+  // Runs the case on every implementation, returning what they did if any
+  // of them disagrees with the first.
+  pub fn check(&self, case: &Case) -> Option<Discrepancy> {
+    let runs = self.run(case);
+
+    if runs.iter().all(|(_, run)| *run == runs[0].1) {
+      return None;
+    }
+
+    Some(Discrepancy {
+      case: case.clone(),
+      runs,
+    })
+  }
+
+  // Shrinks a case the implementations disagree on, one step at a time,
+  // for as long as they still disagree: dropping inputs and runs of program
+  // words, then zeroing and halving words.
+  pub fn minimize(&self, case: Case) -> Case {
+    let mut smallest = case;
+
+    loop {
+      match shrink(&smallest)
+        .into_iter()
+        .find(|candidate| self.check(candidate).is_some())
+      {
+        Some(candidate) => smallest = candidate,
+        None => return smallest,
+      }
+    }
+  }
+
+  fn parameter(&mut self, write: bool) -> Parameter {
+    match self.below(if write { 2 } else { 3 }) {
+      0 => Parameter::Position(self.between(0, SPAN)),
+      1 => Parameter::Relative(self.between(-4, 16)),
+      _ => match self.below(10) {
+        0 => Parameter::Immediate(i64::MAX - self.between(0, 3)),
+        1 => Parameter::Immediate(i64::MIN + self.between(0, 3)),
+        _ => Parameter::Immediate(self.between(-5, 20)),
+      },
+    }
+  }
+
+  fn next(&mut self) -> u64 {
+    self.state ^= self.state << 13;
+    self.state ^= self.state >> 7;
+    self.state ^= self.state << 17;
+    self.state
+  }
+
+  fn below(&mut self, n: u64) -> u64 {
+    self.next() % n
+  }
+
+  // A number in [low, high).
+  fn between(&mut self, low: i64, high: i64) -> i64 {
+    low + self.below((high - low) as u64) as i64
+  }
+}
+
+// Every case one step smaller than `case`.
+fn shrink(case: &Case) -> Vec<Case> {
+  let mut candidates = Vec::new();
+
+  for index in (0..case.inputs.len()).rev() {
+    let mut inputs = case.inputs.clone();
+    inputs.remove(index);
+    candidates.push(Case {
+      program: case.program.clone(),
+      inputs,
+    });
+  }
+
+  // whole instructions first, since dropping a single word shifts every
+  // instruction after it
+  for size in (1..=4).rev() {
+    for index in (0..case.program.len().saturating_sub(size - 1)).rev() {
+      let mut program = case.program.clone();
+      program.drain(index..index + size);
+      candidates.push(Case {
+        program,
+        inputs: case.inputs.clone(),
+      });
+    }
+  }
+
+  for index in 0..case.program.len() {
+    let word = case.program[index];
+    let mut smaller = vec![0, word / 2];
+    smaller.dedup();
+
+    for &value in smaller.iter().filter(|&&value| value != word) {
+      let mut program = case.program.clone();
+      program[index] = value;
+      candidates.push(Case {
+        program,
+        inputs: case.inputs.clone(),
+      });
+    }
+  }
+
+  candidates
+}
+
+// Runs a case on a fresh computer, calling `advance` with what's left of the
+// budget to run it after every interruption, until it halts, faults, runs
+// out of input or uses up the budget.
+fn drive<F>(case: &Case, engine: Engine, advance: F) -> Run
+where
+  F: Fn(&mut Computer, u64) -> Interruption,
+{
+  let mut computer = Computer::new();
+  computer.set_engine(engine);
+  for (address, &word) in case.program.iter().enumerate() {
+    computer
+      .write(address as u64, word)
+      .expect("Failed to load program");
+  }
+
+  let mut inputs = case.inputs.iter();
+  let mut outputs = Vec::new();
+
+  let ending = loop {
+    let budget = BUDGET.saturating_sub(computer.executed());
+    if budget == 0 {
+      break Interruption::BudgetExhausted;
+    }
+
+    match advance(&mut computer, budget) {
+      Interruption::Output => outputs.push(computer.output().expect("Missing output")),
+      Interruption::Input => match inputs.next() {
+        Some(&value) => computer.input(value),
+        None => break Interruption::Input,
+      },
+      Interruption::BudgetExhausted => (),
+      interruption => break interruption,
+    }
+  };
+
+  let memory = computer
+    .memory()
+    .pages()
+    .flat_map(|(base, words)| {
+      words
+        .iter()
+        .enumerate()
+        .filter(|(_, &word)| word != 0)
+        .map(move |(offset, &word)| (base + offset as u64, word))
+    })
+    .collect();
+
+  Run {
+    ending: format!("{:?}", ending),
+    outputs,
+    memory,
+  }
+}
+
+impl fmt::Display for Case {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    writeln!(f, "program {}", join(&self.program))?;
+    write!(f, "inputs  {}", join(&self.inputs))
+  }
+}
+
+impl fmt::Display for Discrepancy {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    writeln!(f, "{}", self.case)?;
+    for (name, run) in self.runs.iter() {
+      write!(
+        f,
+        "\n  {:<12} {}, outputs [{}], memory [{}]",
+        name,
+        run.ending,
+        join(&run.outputs),
+        run
+          .memory
+          .iter()
+          .map(|(address, word)| format!("{}: {}", address, word))
+          .collect::<Vec<String>>()
+          .join(", ")
+      )?;
+    }
+    Ok(())
+  }
+}
+
+fn join(words: &[i64]) -> String {
+  words
+    .iter()
+    .map(|word| word.to_string())
+    .collect::<Vec<String>>()
+    .join(",")
+}
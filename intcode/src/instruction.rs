@@ -0,0 +1,259 @@
+use std::fmt;
+
+#[derive(Debug, PartialEq, Eq, PartialOrd, Ord, Hash, Copy, Clone)]
+pub enum Opcode {
+  Add,
+  Multiply,
+  Input,
+  Output,
+  JumpIfTrue,
+  JumpIfFalse,
+  LessThan,
+  Equals,
+  AdjustRelativeBase,
+  Halt,
+}
+
+impl Opcode {
+  pub fn all() -> [Opcode; 10] {
+    [
+      Opcode::Add,
+      Opcode::Multiply,
+      Opcode::Input,
+      Opcode::Output,
+      Opcode::JumpIfTrue,
+      Opcode::JumpIfFalse,
+      Opcode::LessThan,
+      Opcode::Equals,
+      Opcode::AdjustRelativeBase,
+      Opcode::Halt,
+    ]
+  }
+
+  pub fn from_code(code: i64) -> Option<Opcode> {
+    match code {
+      1 => Some(Opcode::Add),
+      2 => Some(Opcode::Multiply),
+      3 => Some(Opcode::Input),
+      4 => Some(Opcode::Output),
+      5 => Some(Opcode::JumpIfTrue),
+      6 => Some(Opcode::JumpIfFalse),
+      7 => Some(Opcode::LessThan),
+      8 => Some(Opcode::Equals),
+      9 => Some(Opcode::AdjustRelativeBase),
+      99 => Some(Opcode::Halt),
+      _ => None,
+    }
+  }
+
+  pub fn from_mnemonic(mnemonic: &str) -> Option<Opcode> {
+    Opcode::all()
+      .iter()
+      .find(|opcode| opcode.mnemonic() == mnemonic)
+      .copied()
+  }
+
+  pub fn code(self) -> i64 {
+    match self {
+      Opcode::Add => 1,
+      Opcode::Multiply => 2,
+      Opcode::Input => 3,
+      Opcode::Output => 4,
+      Opcode::JumpIfTrue => 5,
+      Opcode::JumpIfFalse => 6,
+      Opcode::LessThan => 7,
+      Opcode::Equals => 8,
+      Opcode::AdjustRelativeBase => 9,
+      Opcode::Halt => 99,
+    }
+  }
+
+  pub fn mnemonic(self) -> &'static str {
+    match self {
+      Opcode::Add => "add",
+      Opcode::Multiply => "mul",
+      Opcode::Input => "in",
+      Opcode::Output => "out",
+      Opcode::JumpIfTrue => "jt",
+      Opcode::JumpIfFalse => "jf",
+      Opcode::LessThan => "lt",
+      Opcode::Equals => "eq",
+      Opcode::AdjustRelativeBase => "arb",
+      Opcode::Halt => "hlt",
+    }
+  }
+
+  pub fn arity(self) -> usize {
+    match self {
+      Opcode::Add | Opcode::Multiply | Opcode::LessThan | Opcode::Equals => 3,
+      Opcode::JumpIfTrue | Opcode::JumpIfFalse => 2,
+      Opcode::Input | Opcode::Output | Opcode::AdjustRelativeBase => 1,
+      Opcode::Halt => 0,
+    }
+  }
+
+  // Index of the parameter the instruction writes its result to, if any.
+  pub fn write_parameter(self) -> Option<usize> {
+    match self {
+      Opcode::Add | Opcode::Multiply | Opcode::LessThan | Opcode::Equals => Some(2),
+      Opcode::Input => Some(0),
+      _ => None,
+    }
+  }
+
+  pub fn is_jump(self) -> bool {
+    matches!(self, Opcode::JumpIfTrue | Opcode::JumpIfFalse)
+  }
+}
+
+#[derive(Debug, PartialEq, Eq, Hash, Copy, Clone)]
+pub enum Parameter {
+  Position(i64),
+  Immediate(i64),
+  Relative(i64),
+}
+
+impl Parameter {
+  pub fn from_mode(mode: i64, value: i64) -> Option<Parameter> {
+    match mode {
+      0 => Some(Parameter::Position(value)),
+      1 => Some(Parameter::Immediate(value)),
+      2 => Some(Parameter::Relative(value)),
+      _ => None,
+    }
+  }
+
+  pub fn mode(self) -> i64 {
+    match self {
+      Parameter::Position(_) => 0,
+      Parameter::Immediate(_) => 1,
+      Parameter::Relative(_) => 2,
+    }
+  }
+
+  pub fn value(self) -> i64 {
+    match self {
+      Parameter::Position(value) | Parameter::Immediate(value) | Parameter::Relative(value) => {
+        value
+      }
+    }
+  }
+}
+
+impl fmt::Display for Parameter {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    match *self {
+      Parameter::Position(addr) => write!(f, "[{}]", addr),
+      Parameter::Immediate(value) => write!(f, "#{}", value),
+      Parameter::Relative(offset) if offset < 0 => write!(f, "[rb{}]", offset),
+      Parameter::Relative(offset) => write!(f, "[rb+{}]", offset),
+    }
+  }
+}
+
+#[derive(Debug, PartialEq, Eq, Hash, Clone)]
+pub struct Instruction {
+  pub opcode: Opcode,
+  pub params: Vec<Parameter>,
+}
+
+impl Instruction {
+  // Decodes the instruction at the start of `words`. Returns `None` when it
+  // doesn't decode cleanly: unknown opcode, invalid or leftover parameter
+  // modes, a write parameter in immediate mode, or not enough words left.
+  pub fn decode(words: &[i64]) -> Option<Instruction> {
+    let raw = *words.first()?;
+    if raw < 0 {
+      return None;
+    }
+
+    let opcode = Opcode::from_code(raw % 100)?;
+    let mut modes = raw / 100;
+    let mut params = Vec::with_capacity(opcode.arity());
+
+    for index in 0..opcode.arity() {
+      let param = Parameter::from_mode(modes % 10, *words.get(index + 1)?)?;
+
+      if opcode.write_parameter() == Some(index) {
+        if let Parameter::Immediate(_) = param {
+          return None;
+        }
+      }
+
+      params.push(param);
+      modes /= 10;
+    }
+
+    if modes != 0 {
+      return None;
+    }
+
+    Some(Instruction { opcode, params })
+  }
+
+  // Number of words the instruction takes in memory.
+  pub fn size(&self) -> usize {
+    self.params.len() + 1
+  }
+
+  pub fn encode(&self) -> Vec<i64> {
+    let mut words = Vec::with_capacity(self.size());
+    let mut raw = self.opcode.code();
+    let mut scale = 100;
+
+    for param in self.params.iter() {
+      raw += param.mode() * scale;
+      scale *= 10;
+    }
+
+    words.push(raw);
+    words.extend(self.params.iter().map(|param| param.value()));
+    words
+  }
+
+  // Immediate jump target of opcodes 5/6, when it's known statically.
+  pub fn jump_target(&self) -> Option<i64> {
+    if !self.opcode.is_jump() {
+      return None;
+    }
+
+    match self.params[1] {
+      Parameter::Immediate(target) => Some(target),
+      _ => None,
+    }
+  }
+
+  // Formats the instruction, letting the caller choose how each operand is
+  // rendered (the disassembler uses this to print labels).
+  pub fn format_with<F>(&self, f: &mut dyn fmt::Write, mut operand: F) -> fmt::Result
+  where
+    F: FnMut(usize, Parameter) -> String,
+  {
+    write!(f, "{}", self.opcode.mnemonic())?;
+
+    let write_param = self.opcode.write_parameter();
+    let mut first = true;
+
+    for (index, &param) in self.params.iter().enumerate() {
+      if Some(index) == write_param {
+        write!(f, " -> {}", operand(index, param))?;
+      } else {
+        write!(
+          f,
+          "{}{}",
+          if first { " " } else { ", " },
+          operand(index, param)
+        )?;
+        first = false;
+      }
+    }
+
+    Ok(())
+  }
+}
+
+impl fmt::Display for Instruction {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    self.format_with(f, |_, param| param.to_string())
+  }
+}
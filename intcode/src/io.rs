@@ -0,0 +1,142 @@
+use crate::ascii::is_ascii;
+use std::collections::VecDeque;
+use std::io::{BufRead, Read, Write};
+use std::sync::mpsc::{Receiver, Sender};
+
+// Where a computer pulls its input from once its input buffer runs dry.
+// Returning `None` means there's nothing to read (yet), and the computer
+// interrupts with `Interruption::Input` as usual.
+pub trait InputSource: Send {
+  fn next_input(&mut self) -> Option<i64>;
+}
+
+// Where a computer sends its output instead of buffering it. A computer with
+// an output sink doesn't interrupt on output.
+pub trait OutputSink: Send {
+  fn send_output(&mut self, value: i64);
+}
+
+// Input from an iterator.
+pub struct IterInput<I: Iterator<Item = i64> + Send>(pub I);
+
+impl<I: Iterator<Item = i64> + Send> InputSource for IterInput<I> {
+  fn next_input(&mut self) -> Option<i64> {
+    self.0.next()
+  }
+}
+
+// Input from a closure.
+pub struct FnInput<F: FnMut() -> Option<i64> + Send>(pub F);
+
+impl<F: FnMut() -> Option<i64> + Send> InputSource for FnInput<F> {
+  fn next_input(&mut self) -> Option<i64> {
+    (self.0)()
+  }
+}
+
+// Output to a closure.
+pub struct FnOutput<F: FnMut(i64) + Send>(pub F);
+
+impl<F: FnMut(i64) + Send> OutputSink for FnOutput<F> {
+  fn send_output(&mut self, value: i64) {
+    (self.0)(value)
+  }
+}
+
+// Blocks until a value arrives, and runs dry once every sender is gone.
+impl InputSource for Receiver<i64> {
+  fn next_input(&mut self) -> Option<i64> {
+    self.recv().ok()
+  }
+}
+
+// Outputs sent after the receiver is gone are dropped.
+impl OutputSink for Sender<i64> {
+  fn send_output(&mut self, value: i64) {
+    self.send(value).ok();
+  }
+}
+
+impl OutputSink for Vec<i64> {
+  fn send_output(&mut self, value: i64) {
+    self.push(value);
+  }
+}
+
+// Integers read from text, separated by whitespace or commas.
+pub struct NumberInput<R: BufRead + Send> {
+  reader: R,
+  pending: VecDeque<i64>,
+}
+
+impl<R: BufRead + Send> NumberInput<R> {
+  pub fn new(reader: R) -> NumberInput<R> {
+    NumberInput {
+      reader,
+      pending: VecDeque::new(),
+    }
+  }
+}
+
+impl<R: BufRead + Send> InputSource for NumberInput<R> {
+  fn next_input(&mut self) -> Option<i64> {
+    while self.pending.is_empty() {
+      let mut line = String::new();
+      if self
+        .reader
+        .read_line(&mut line)
+        .expect("Failed to read input")
+        == 0
+      {
+        return None;
+      }
+
+      for word in line.split(|c: char| c.is_whitespace() || c == ',') {
+        if !word.is_empty() {
+          self
+            .pending
+            .push_back(word.parse().expect("Invalid number from input"));
+        }
+      }
+    }
+
+    self.pending.pop_front()
+  }
+}
+
+// Every byte read is one input value, for programs that speak ASCII.
+pub struct AsciiInput<R: Read + Send>(pub R);
+
+impl<R: Read + Send> InputSource for AsciiInput<R> {
+  fn next_input(&mut self) -> Option<i64> {
+    let mut byte = [0];
+    match self.0.read(&mut byte).expect("Failed to read input") {
+      0 => None,
+      _ => Some(byte[0] as i64),
+    }
+  }
+}
+
+// Writes every value on its own line.
+pub struct NumberOutput<W: Write + Send>(pub W);
+
+impl<W: Write + Send> OutputSink for NumberOutput<W> {
+  fn send_output(&mut self, value: i64) {
+    writeln!(self.0, "{}", value).expect("Failed to write output");
+  }
+}
+
+// Writes ASCII values as characters, and anything else as a number on its
+// own line.
+pub struct AsciiOutput<W: Write + Send>(pub W);
+
+impl<W: Write + Send> OutputSink for AsciiOutput<W> {
+  fn send_output(&mut self, value: i64) {
+    if is_ascii(value) {
+      self.0.write_all(&[value as u8])
+    } else {
+      writeln!(self.0, "{}", value)
+    }
+    .expect("Failed to write output");
+  }
+}
@@ -0,0 +1,62 @@
+mod ascii;
+mod assembler;
+mod cache;
+mod computer;
+mod conformance;
+mod coverage;
+mod debugger;
+mod decompiler;
+mod disassembler;
+mod fault;
+mod flow;
+mod fuzz;
+mod instruction;
+mod io;
+mod machine;
+mod memory;
+mod network;
+mod optimizer;
+mod plugin;
+mod profiler;
+mod runner;
+mod snapshot;
+#[cfg(test)]
+mod tests;
+mod tracer;
+mod transcript;
+mod transpiler;
+mod word;
+
+pub use crate::ascii::{is_ascii, Ascii};
+pub use crate::assembler::{assemble, format_program, AssembleError};
+pub use crate::computer::{parse, Arithmetic, Computer, Engine, Interruption, State};
+pub use crate::conformance::{check_conformance, Example, Failure, EXAMPLES};
+pub use crate::coverage::{Branch, Coverage};
+pub use crate::debugger::Debugger;
+pub use crate::decompiler::decompile;
+pub use crate::disassembler::{disassemble, label_name, Line, Listing};
+pub use crate::fault::Fault;
+pub use crate::flow::{analyze, Block, Edge, EdgeKind, Exit, FlowGraph, Modification};
+pub use crate::fuzz::{Case, Discrepancy, Fuzzer, Implementation, Run};
+pub use crate::instruction::{Instruction, Opcode, Parameter};
+pub use crate::io::{
+  AsciiInput, AsciiOutput, FnInput, FnOutput, InputSource, IterInput, NumberInput, NumberOutput,
+  OutputSink,
+};
+pub use crate::machine::Machine;
+pub use crate::memory::{Memory, PAGE_SIZE};
+pub use crate::network::{Network, Outcome, Report};
+pub use crate::optimizer::{Candidate, Optimizer, Topology};
+pub use crate::plugin::{Access, Effect, Handler, Plugin, Registry};
+pub use crate::profiler::Profile;
+pub use crate::runner::{chain, ring, spawn, Finished, Handle};
+pub use crate::snapshot::{Snapshot, SnapshotError};
+pub use crate::tracer::{
+  tracer_from_env, JsonTracer, NoopTracer, Operation, PrettyTracer, TraceEvent, Tracer,
+};
+pub use crate::transcript::{
+  recorder_from_env, replay, Actual, Divergence, Entry, Recorder, Transcript, TranscriptError,
+};
+pub use crate::transpiler::transpile;
+pub use crate::word::Word;
+pub use num_bigint::BigInt;
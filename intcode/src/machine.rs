@@ -0,0 +1,48 @@
+use crate::computer::{Computer, Interruption, State};
+use crate::fault::Fault;
+use crate::word::{narrow, Word};
+
+// The contract every way of running an Intcode program honours, so hosts and
+// tests can drive any of them: the interpreter's `Computer`, and the
+// programs `transpile` compiles to Rust.
+pub trait Machine {
+  fn input(&mut self, value: i64);
+  fn output(&mut self) -> Option<i64>;
+  fn run(&mut self) -> Interruption;
+  fn read(&self, index: u64) -> i64;
+  fn write(&mut self, index: u64, value: i64) -> Result<(), Fault>;
+  fn get_state(&self) -> State;
+  fn executed(&self) -> u64;
+}
+
+// Computers with wider words are driven with i64s too, their words narrowed
+// on the way out.
+impl<W: Word> Machine for Computer<W> {
+  fn input(&mut self, value: i64) {
+    Computer::input(self, W::from_i64(value))
+  }
+
+  fn output(&mut self) -> Option<i64> {
+    Computer::output(self).map(|value| narrow(&value))
+  }
+
+  fn run(&mut self) -> Interruption {
+    Computer::run(self)
+  }
+
+  fn read(&self, index: u64) -> i64 {
+    narrow(&Computer::read(self, index))
+  }
+
+  fn write(&mut self, index: u64, value: i64) -> Result<(), Fault> {
+    Computer::write(self, index, W::from_i64(value))
+  }
+
+  fn get_state(&self) -> State {
+    Computer::get_state(self)
+  }
+
+  fn executed(&self) -> u64 {
+    Computer::executed(self)
+  }
+}
@@ -0,0 +1,99 @@
+use crate::word::Word;
+use std::collections::BTreeMap;
+use std::sync::Arc;
+
+const PAGE_BITS: u32 = 10;
+pub const PAGE_SIZE: usize = 1 << PAGE_BITS;
+// Pages below this are indexed directly, as that's where programs, their
+// stacks and their data live; the rest are looked up by page number.
+const NEAR_PAGES: u64 = 64;
+
+type Page<W> = [W; PAGE_SIZE];
+
+// Paged memory for the computer. Pages are only allocated when something is
+// written to them (reading untouched memory yields 0), so a write far out
+// only costs the page it lands on. They're shared between clones until one
+// of them writes to the page, at which point it gets its own copy.
+#[derive(Debug, Clone, Default)]
+pub struct Memory<W: Word = i64> {
+  near: Vec<Option<Arc<Page<W>>>>,
+  far: BTreeMap<u64, Arc<Page<W>>>,
+  high_water_mark: u64,
+}
+
+impl<W: Word> Memory<W> {
+  pub fn new() -> Memory<W> {
+    Memory {
+      near: Vec::new(),
+      far: BTreeMap::new(),
+      high_water_mark: 0,
+    }
+  }
+
+  pub fn read(&self, addr: u64) -> W {
+    let number = addr >> PAGE_BITS;
+    let offset = addr as usize & (PAGE_SIZE - 1);
+
+    let page = if number < NEAR_PAGES {
+      self.near.get(number as usize).and_then(Option::as_ref)
+    } else {
+      self.far.get(&number)
+    };
+
+    match page {
+      Some(page) => page[offset].clone(),
+      None => W::default(),
+    }
+  }
+
+  pub fn write(&mut self, addr: u64, value: W) {
+    let number = addr >> PAGE_BITS;
+    let offset = addr as usize & (PAGE_SIZE - 1);
+    let new_page = || Arc::new(std::array::from_fn(|_| W::default()));
+
+    let page = if number < NEAR_PAGES {
+      if number as usize >= self.near.len() {
+        self.near.resize(number as usize + 1, None);
+      }
+      self.near[number as usize].get_or_insert_with(new_page)
+    } else {
+      self.far.entry(number).or_insert_with(new_page)
+    };
+    Arc::make_mut(page)[offset] = value;
+
+    if addr >= self.high_water_mark {
+      self.high_water_mark = addr.saturating_add(1);
+    }
+  }
+
+  // One past the highest address ever written to.
+  pub fn high_water_mark(&self) -> u64 {
+    self.high_water_mark
+  }
+
+  // Base address and contents of every allocated page, in address order.
+  pub fn pages(&self) -> impl Iterator<Item = (u64, &[W])> + '_ {
+    self
+      .near
+      .iter()
+      .enumerate()
+      .filter_map(|(number, page)| page.as_ref().map(|page| (number as u64, page)))
+      .chain(self.far.iter().map(|(&number, page)| (number, page)))
+      .map(|(number, page)| (number << PAGE_BITS, &page[..]))
+  }
+
+  pub fn allocated_pages(&self) -> usize {
+    self.pages().count()
+  }
+
+  // Number of pages this memory still shares with some clone.
+  pub fn shared_pages(&self) -> usize {
+    self
+      .near
+      .iter()
+      .flatten()
+      .chain(self.far.values())
+      .filter(|page| Arc::strong_count(page) > 1)
+      .count()
+  }
+}
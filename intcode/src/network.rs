@@ -0,0 +1,245 @@
+use crate::computer::{Computer, Interruption};
+use crate::fault::Fault;
+use std::collections::BTreeMap;
+
+// Instructions a node gets to run before the next node has its turn, so a
+// node that never waits for input can't starve the rest of the network.
+const TIME_SLICE: u64 = 10_000;
+
+#[derive(Debug, PartialEq, Eq, Copy, Clone)]
+pub enum Outcome {
+  // every node halted (or faulted)
+  Halted,
+  // some nodes are still waiting for input nobody is going to send
+  Quiescent,
+  // the run used up the network's instruction budget with nodes still
+  // runnable
+  BudgetExhausted,
+}
+
+#[derive(Debug, Clone)]
+pub struct Report {
+  pub outcome: Outcome,
+  // every value sent along each named output edge
+  pub outputs: BTreeMap<String, Vec<i64>>,
+  pub faults: Vec<(String, Fault)>,
+}
+
+#[derive(Debug, PartialEq, Eq, Copy, Clone)]
+enum Status {
+  Runnable,
+  Waiting,
+  Stopped,
+}
+
+#[derive(Debug)]
+enum Edge {
+  Node(usize),
+  Output(String),
+}
+
+#[derive(Debug)]
+struct Node {
+  name: String,
+  computer: Computer,
+  edges: Vec<Edge>,
+  status: Status,
+}
+
+// A directed graph of computers. Every output of a node is copied along each
+// of its outgoing edges, either into another node's input or onto a named
+// output edge that ends up in the report. Nodes with several incoming edges
+// get the values in the order they were sent.
+//
+//   let mut network = Network::new();
+//   network.add_node("a", computer_a, &[phase_a]);
+//   network.add_node("b", computer_b, &[phase_b]);
+//   network.connect("a", "b");
+//   network.connect("b", "a");
+//   network.add_output("b", "thrusters");
+//   network.input("a", 0);
+//   let report = network.run();
+#[derive(Debug, Default)]
+pub struct Network {
+  nodes: Vec<Node>,
+  budget: Option<u64>,
+}
+
+impl Network {
+  pub fn new() -> Network {
+    Network {
+      nodes: Vec::new(),
+      budget: None,
+    }
+  }
+
+  // Caps the instructions all nodes together may execute in each `run`, so
+  // a network that never settles still returns.
+  pub fn set_budget(&mut self, instructions: u64) {
+    self.budget = Some(instructions);
+  }
+
+  // Adds a node, queueing `initial` as its first inputs (phase settings and
+  // the like).
+  pub fn add_node(&mut self, name: &str, mut computer: Computer, initial: &[i64]) {
+    if self.find(name).is_some() {
+      panic!("node '{}' added twice", name);
+    }
+
+    for &value in initial.iter() {
+      computer.input(value);
+    }
+
+    self.nodes.push(Node {
+      name: name.to_string(),
+      computer,
+      edges: Vec::new(),
+      status: Status::Runnable,
+    });
+  }
+
+  // Sends every output of `from` to the input of `to`.
+  pub fn connect(&mut self, from: &str, to: &str) {
+    let to = self.index(to);
+    let from = self.index(from);
+    self.nodes[from].edges.push(Edge::Node(to));
+  }
+
+  // Reports every output of `from` under `name`.
+  pub fn add_output(&mut self, from: &str, name: &str) {
+    let from = self.index(from);
+    self.nodes[from].edges.push(Edge::Output(name.to_string()));
+  }
+
+  // Queues an input for a node from outside the network.
+  pub fn input(&mut self, node: &str, value: i64) {
+    let index = self.index(node);
+    self.deliver(index, value);
+  }
+
+  pub fn computer(&self, node: &str) -> &Computer {
+    &self.nodes[self.index(node)].computer
+  }
+
+  // Runs the nodes in turn until every one of them has halted or is waiting
+  // for input that will never come, or the budget runs out. The network can
+  // be given more input and run again afterwards.
+  pub fn run(&mut self) -> Report {
+    let mut outputs: BTreeMap<String, Vec<i64>> = BTreeMap::new();
+    let mut faults = Vec::new();
+
+    for node in self.nodes.iter() {
+      for edge in node.edges.iter() {
+        if let Edge::Output(name) = edge {
+          outputs.entry(name.clone()).or_default();
+        }
+      }
+    }
+
+    let mut remaining = self.budget.unwrap_or(u64::MAX);
+
+    'run: loop {
+      let mut progress = false;
+
+      for index in 0..self.nodes.len() {
+        if self.nodes[index].status != Status::Runnable {
+          continue;
+        }
+        if remaining == 0 {
+          break 'run;
+        }
+        progress = true;
+
+        let slice = TIME_SLICE.min(remaining);
+        let start = self.nodes[index].computer.executed();
+        loop {
+          let used = self.nodes[index].computer.executed() - start;
+          if used >= slice {
+            break;
+          }
+
+          match self.nodes[index].computer.run_for(slice - used) {
+            Interruption::Output => {
+              let value = self.nodes[index]
+                .computer
+                .output()
+                .expect("Failed to retrieve output from computer");
+              self.send(index, value, &mut outputs);
+            }
+            Interruption::BudgetExhausted => break,
+            Interruption::Input => {
+              self.nodes[index].status = Status::Waiting;
+              break;
+            }
+            Interruption::Halt => {
+              self.nodes[index].status = Status::Stopped;
+              break;
+            }
+            Interruption::Fault(fault) => {
+              faults.push((self.nodes[index].name.clone(), fault));
+              self.nodes[index].status = Status::Stopped;
+              break;
+            }
+          }
+        }
+        remaining -= self.nodes[index].computer.executed() - start;
+      }
+
+      if !progress {
+        break;
+      }
+    }
+
+    let outcome = if self.nodes.iter().all(|node| node.status == Status::Stopped) {
+      Outcome::Halted
+    } else if self
+      .nodes
+      .iter()
+      .any(|node| node.status == Status::Runnable)
+    {
+      Outcome::BudgetExhausted
+    } else {
+      Outcome::Quiescent
+    };
+
+    Report {
+      outcome,
+      outputs,
+      faults,
+    }
+  }
+
+  fn send(&mut self, from: usize, value: i64, outputs: &mut BTreeMap<String, Vec<i64>>) {
+    let mut targets = Vec::new();
+
+    for edge in self.nodes[from].edges.iter() {
+      match edge {
+        Edge::Node(to) => targets.push(*to),
+        Edge::Output(name) => outputs.entry(name.clone()).or_default().push(value),
+      }
+    }
+
+    for to in targets {
+      self.deliver(to, value);
+    }
+  }
+
+  fn deliver(&mut self, to: usize, value: i64) {
+    let node = &mut self.nodes[to];
+    node.computer.input(value);
+
+    if node.status == Status::Waiting {
+      node.status = Status::Runnable;
+    }
+  }
+
+  fn find(&self, name: &str) -> Option<usize> {
+    self.nodes.iter().position(|node| node.name == name)
+  }
+
+  fn index(&self, name: &str) -> usize {
+    self
+      .find(name)
+      .unwrap_or_else(|| panic!("no node named '{}'", name))
+  }
+}
@@ -0,0 +1,227 @@
+use crate::computer::Computer;
+use crate::network::{Network, Outcome};
+use std::cmp::Ordering;
+use std::sync::Mutex;
+use std::thread;
+
+// Instructions a whole circuit may execute for one ordering before it's
+// given up on.
+const BUDGET: u64 = 10_000_000;
+
+#[derive(Debug, PartialEq, Eq, Copy, Clone)]
+pub enum Topology {
+  // each amplifier feeds the next one
+  Chain,
+  // like `Chain`, with the last amplifier feeding back into the first
+  Loop,
+}
+
+impl Topology {
+  // Wires up one amplifier per phase setting, all running copies of
+  // `prototype`. The first amplifier gets `0` after its phase setting and the
+  // last one's outputs are reported under "output".
+  pub fn build(self, prototype: &Computer, phases: &[i64]) -> Network {
+    let names: Vec<String> = (0..phases.len()).map(|i| i.to_string()).collect();
+    let mut network = Network::new();
+
+    for (name, &phase) in names.iter().zip(phases.iter()) {
+      network.add_node(name, prototype.clone(), &[phase]);
+    }
+
+    for pair in names.windows(2) {
+      network.connect(&pair[0], &pair[1]);
+    }
+
+    if let (Some(first), Some(last)) = (names.first(), names.last()) {
+      if self == Topology::Loop {
+        network.connect(last, first);
+      }
+      network.add_output(last, "output");
+      network.input(first, 0);
+    }
+
+    network
+  }
+}
+
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct Candidate {
+  pub phases: Vec<i64>,
+  pub score: i64,
+}
+
+// Tries every ordering of a phase alphabet on an amplifier circuit, spread
+// over a few worker threads. Repeated phases in the alphabet count once. The
+// objective scores the values the last amplifier output (higher is better);
+// orderings it returns `None` for, or that make an amplifier fault or run
+// past the budget, are left out.
+pub struct Optimizer<F> {
+  prototype: Computer,
+  topology: Topology,
+  objective: F,
+  workers: usize,
+  budget: u64,
+}
+
+impl<F> Optimizer<F>
+where
+  F: Fn(&[i64]) -> Option<i64> + Sync,
+{
+  pub fn new(prototype: Computer, topology: Topology, objective: F) -> Optimizer<F> {
+    let workers = thread::available_parallelism()
+      .map(|n| n.get())
+      .unwrap_or(1);
+
+    Optimizer {
+      prototype,
+      topology,
+      objective,
+      workers,
+      budget: BUDGET,
+    }
+  }
+
+  pub fn set_workers(&mut self, workers: usize) {
+    self.workers = workers.max(1);
+  }
+
+  // Instructions the circuit may execute for each ordering.
+  pub fn set_budget(&mut self, instructions: u64) {
+    self.budget = instructions;
+  }
+
+  // The best ordering of `alphabet`. Ties go to the smallest ordering, so the
+  // answer doesn't depend on how the work was split up.
+  pub fn best(&self, alphabet: &[i64]) -> Option<Candidate> {
+    self.evaluate(alphabet).into_iter().min_by(rank)
+  }
+
+  // Every scored ordering of `alphabet`, best first.
+  pub fn ranked(&self, alphabet: &[i64]) -> Vec<Candidate> {
+    let mut candidates = self.evaluate(alphabet);
+    candidates.sort_by(rank);
+    candidates
+  }
+
+  fn evaluate(&self, alphabet: &[i64]) -> Vec<Candidate> {
+    // orderings are handed out one at a time to whichever worker is free
+    let permutations = Mutex::new(Permutations::new(alphabet));
+
+    thread::scope(|scope| {
+      let threads: Vec<_> = (0..self.workers)
+        .map(|_| {
+          // computers aren't Sync, so every worker gets its own prototype
+          let prototype = self.prototype.clone();
+          let permutations = &permutations;
+          let (topology, objective, budget) = (self.topology, &self.objective, self.budget);
+
+          scope.spawn(move || {
+            let mut candidates = Vec::new();
+            loop {
+              let next = permutations
+                .lock()
+                .expect("Failed to lock permutations")
+                .next();
+              let phases = match next {
+                Some(phases) => phases,
+                None => return candidates,
+              };
+              candidates.extend(score(topology, objective, budget, &prototype, &phases));
+            }
+          })
+        })
+        .collect();
+
+      threads
+        .into_iter()
+        .flat_map(|thread| thread.join().expect("Optimizer thread panicked"))
+        .collect()
+    })
+  }
+}
+
+fn score<F>(
+  topology: Topology,
+  objective: &F,
+  budget: u64,
+  prototype: &Computer,
+  phases: &[i64],
+) -> Option<Candidate>
+where
+  F: Fn(&[i64]) -> Option<i64>,
+{
+  let mut network = topology.build(prototype, phases);
+  network.set_budget(budget);
+
+  let report = network.run();
+  if !report.faults.is_empty() || report.outcome == Outcome::BudgetExhausted {
+    return None;
+  }
+
+  let score = objective(report.outputs.get("output")?)?;
+  Some(Candidate {
+    phases: phases.to_vec(),
+    score,
+  })
+}
+
+fn rank(a: &Candidate, b: &Candidate) -> Ordering {
+  b.score.cmp(&a.score).then_with(|| a.phases.cmp(&b.phases))
+}
+
+// Every ordering of a phase alphabet, generated one at a time with Heap's
+// algorithm. Repeated phases are only used once, so no ordering comes up
+// twice.
+struct Permutations {
+  items: Vec<i64>,
+  counters: Vec<usize>,
+  index: usize,
+  started: bool,
+}
+
+impl Permutations {
+  fn new(alphabet: &[i64]) -> Permutations {
+    let mut items = Vec::with_capacity(alphabet.len());
+    for &item in alphabet.iter() {
+      if !items.contains(&item) {
+        items.push(item);
+      }
+    }
+
+    Permutations {
+      counters: vec![0; items.len()],
+      items,
+      index: 1,
+      started: false,
+    }
+  }
+}
+
+impl Iterator for Permutations {
+  type Item = Vec<i64>;
+
+  fn next(&mut self) -> Option<Vec<i64>> {
+    if !self.started {
+      self.started = true;
+      return Some(self.items.clone());
+    }
+
+    while self.index < self.items.len() {
+      if self.counters[self.index] < self.index {
+        if self.index.is_multiple_of(2) {
+          self.items.swap(0, self.index);
+        } else {
+          self.items.swap(self.counters[self.index], self.index);
+        }
+        self.counters[self.index] += 1;
+        self.index = 1;
+        return Some(self.items.clone());
+      }
+
+      self.counters[self.index] = 0;
+      self.index += 1;
+    }
+
+    None
+  }
+}
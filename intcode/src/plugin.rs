@@ -0,0 +1,127 @@
+use crate::instruction::Opcode;
+use crate::word::Word;
+use std::collections::HashMap;
+use std::fmt;
+use std::sync::Arc;
+
+// Parameter modes are the digits of the instruction above the opcode, and an
+// i64 only has room for this many of them.
+const MAX_ARITY: usize = 16;
+
+// How a plug-in opcode uses each of its parameters. Read parameters accept
+// any mode; write parameters fault in immediate mode, like the built-in ones.
+#[derive(Debug, PartialEq, Eq, Copy, Clone)]
+pub enum Access {
+  Read,
+  Write,
+}
+
+// What a plug-in wants the computer to do after it ran.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub enum Effect<W> {
+  Continue,
+  // writes the value to the write parameter, then continues
+  Store(W),
+  Jump(u64),
+  Output(W),
+  Halt,
+}
+
+pub type Handler<W> = dyn Fn(&[W]) -> Effect<W> + Send + Sync;
+
+// An extra opcode. The handler gets the value of every parameter (for the
+// write parameter, whatever its address currently holds).
+#[derive(Clone)]
+pub struct Plugin<W> {
+  pub name: String,
+  pub params: Vec<Access>,
+  pub handler: Arc<Handler<W>>,
+}
+
+impl<W> Plugin<W> {
+  pub fn arity(&self) -> usize {
+    self.params.len()
+  }
+
+  pub fn write_parameter(&self) -> Option<usize> {
+    self
+      .params
+      .iter()
+      .position(|&access| access == Access::Write)
+  }
+}
+
+impl<W> fmt::Debug for Plugin<W> {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    write!(
+      f,
+      "Plugin {{ name: {:?}, params: {:?} }}",
+      self.name, self.params
+    )
+  }
+}
+
+// Opcodes installed on top of the standard instruction set. The computer
+// only looks here for opcodes it doesn't know itself, so the built-in ones
+// can't be replaced and cost nothing extra.
+#[derive(Debug, Clone)]
+pub struct Registry<W> {
+  plugins: HashMap<i64, Arc<Plugin<W>>>,
+}
+
+impl<W> Default for Registry<W> {
+  fn default() -> Registry<W> {
+    Registry {
+      plugins: HashMap::new(),
+    }
+  }
+}
+
+impl<W: Word> Registry<W> {
+  pub fn new() -> Registry<W> {
+    Registry::default()
+  }
+
+  // Installs `handler` as opcode `code`, replacing any plug-in already
+  // installed there.
+  pub fn register<F>(&mut self, code: i64, name: &str, params: &[Access], handler: F)
+  where
+    F: Fn(&[W]) -> Effect<W> + Send + Sync + 'static,
+  {
+    if !(1..100).contains(&code) || Opcode::from_code(code).is_some() {
+      panic!("opcode {} isn't available for plug-ins", code);
+    }
+    if params.len() > MAX_ARITY {
+      panic!("opcode {} has more than {} parameters", code, MAX_ARITY);
+    }
+    if params
+      .iter()
+      .filter(|&&access| access == Access::Write)
+      .count()
+      > 1
+    {
+      panic!("opcode {} has more than one write parameter", code);
+    }
+
+    self.plugins.insert(
+      code,
+      Arc::new(Plugin {
+        name: name.to_string(),
+        params: params.to_vec(),
+        handler: Arc::new(handler),
+      }),
+    );
+  }
+
+  pub fn unregister(&mut self, code: i64) -> Option<Arc<Plugin<W>>> {
+    self.plugins.remove(&code)
+  }
+
+  pub fn get(&self, code: i64) -> Option<&Arc<Plugin<W>>> {
+    self.plugins.get(&code)
+  }
+
+  pub fn is_empty(&self) -> bool {
+    self.plugins.is_empty()
+  }
+}
@@ -0,0 +1,151 @@
+use crate::instruction::{Instruction, Opcode};
+use crate::tracer::{Operation, TraceEvent};
+use std::collections::{BTreeMap, HashMap};
+use std::fmt::Write;
+
+const REPORT_ROWS: usize = 20;
+
+// Execution counts gathered while the computer runs with profiling on.
+#[derive(Debug, Clone, Default)]
+pub struct Profile {
+  total: u64,
+  addresses: HashMap<u64, u64>,
+  instructions: HashMap<u64, Instruction>,
+  opcodes: BTreeMap<Opcode, u64>,
+  back_edges: HashMap<(u64, u64), u64>,
+}
+
+impl Profile {
+  pub fn new() -> Profile {
+    Profile::default()
+  }
+
+  // Counts a built-in instruction that executed; plug-in opcodes aren't
+  // profiled.
+  pub fn record(&mut self, event: &TraceEvent) {
+    let instruction = match &event.operation {
+      Operation::Instruction(instruction) => instruction,
+      _ => return,
+    };
+    let opcode = instruction.opcode;
+
+    self.total += 1;
+    *self.addresses.entry(event.pc).or_insert(0) += 1;
+    *self.opcodes.entry(opcode).or_insert(0) += 1;
+    self
+      .instructions
+      .entry(event.pc)
+      .or_insert_with(|| instruction.clone());
+
+    // a taken jump backwards (or onto itself) closes a loop
+    if opcode.is_jump() {
+      let taken = (event.values[0] != 0) == (opcode == Opcode::JumpIfTrue);
+      let target = event.values[1];
+
+      if taken && target >= 0 && target as u64 <= event.pc {
+        *self
+          .back_edges
+          .entry((event.pc, target as u64))
+          .or_insert(0) += 1;
+      }
+    }
+  }
+
+  pub fn total(&self) -> u64 {
+    self.total
+  }
+
+  pub fn count(&self, addr: u64) -> u64 {
+    self.addresses.get(&addr).copied().unwrap_or(0)
+  }
+
+  // Instruction addresses with their execution counts, hottest first.
+  pub fn hot_addresses(&self) -> Vec<(u64, u64)> {
+    ranked(self.addresses.iter().map(|(&addr, &count)| (addr, count)))
+  }
+
+  pub fn opcode_histogram(&self) -> Vec<(Opcode, u64)> {
+    ranked(self.opcodes.iter().map(|(&opcode, &count)| (opcode, count)))
+  }
+
+  // Taken backward jumps as (from, to), hottest first.
+  pub fn back_edges(&self) -> Vec<((u64, u64), u64)> {
+    ranked(self.back_edges.iter().map(|(&edge, &count)| (edge, count)))
+  }
+
+  pub fn report(&self) -> String {
+    let mut text = String::new();
+    let percent = |count: u64| 100.0 * count as f64 / self.total.max(1) as f64;
+
+    writeln!(text, "{} instructions executed", self.total).unwrap();
+
+    writeln!(text, "\nhot instructions:").unwrap();
+    for &(addr, count) in self.hot_addresses().iter().take(REPORT_ROWS) {
+      writeln!(
+        text,
+        "  {:>6} {:>12} {:>6.2}%  {}",
+        addr,
+        count,
+        percent(count),
+        self.instructions[&addr]
+      )
+      .unwrap();
+    }
+
+    writeln!(text, "\nopcodes:").unwrap();
+    for &(opcode, count) in self.opcode_histogram().iter() {
+      writeln!(
+        text,
+        "  {:<6} {:>12} {:>6.2}%",
+        opcode.mnemonic(),
+        count,
+        percent(count)
+      )
+      .unwrap();
+    }
+
+    writeln!(text, "\nhot loops:").unwrap();
+    for &((from, to), count) in self.back_edges().iter().take(REPORT_ROWS) {
+      writeln!(
+        text,
+        "  {:>6} -> {:<6} {:>12} iterations, {} words",
+        from,
+        to,
+        count,
+        from - to
+      )
+      .unwrap();
+    }
+
+    text
+  }
+
+  // The same data as `report`, as CSV with one row per address, opcode and
+  // back-edge.
+  pub fn to_csv(&self) -> String {
+    let mut text = String::from("kind,address,target,opcode,count\n");
+
+    for &(addr, count) in self.hot_addresses().iter() {
+      let opcode = self.instructions[&addr].opcode.mnemonic();
+      writeln!(text, "instruction,{},,{},{}", addr, opcode, count).unwrap();
+    }
+
+    for &(opcode, count) in self.opcode_histogram().iter() {
+      writeln!(text, "opcode,,,{},{}", opcode.mnemonic(), count).unwrap();
+    }
+
+    for &((from, to), count) in self.back_edges().iter() {
+      let opcode = self.instructions[&from].opcode.mnemonic();
+      writeln!(text, "back-edge,{},{},{},{}", from, to, opcode, count).unwrap();
+    }
+
+    text
+  }
+}
+
+// Sorts by count, highest first, breaking ties by key so reports are stable.
+fn ranked<K: Ord, I: Iterator<Item = (K, u64)>>(items: I) -> Vec<(K, u64)> {
+  let mut items: Vec<(K, u64)> = items.collect();
+  items.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+  items
+}
@@ -0,0 +1,103 @@
+use crate::computer::{Computer, Interruption, State};
+use std::sync::mpsc;
+use std::sync::mpsc::{Receiver, Sender};
+use std::thread;
+use std::thread::JoinHandle;
+
+// What a computer left behind when its thread finished: the machine itself,
+// everything it output, and why it stopped. A computer stops when it halts,
+// faults, or needs input after every sender to its input channel is gone.
+#[derive(Debug)]
+pub struct Finished {
+  pub computer: Computer,
+  pub state: State,
+  pub outputs: Vec<i64>,
+  pub interruption: Interruption,
+}
+
+pub struct Handle {
+  thread: JoinHandle<Finished>,
+}
+
+impl Handle {
+  pub fn join(self) -> Finished {
+    self.thread.join().expect("Computer thread panicked")
+  }
+}
+
+// Runs `computer` on its own thread, reading input from `input` and sending
+// every output to `output`. The output channel is closed once the computer
+// stops, so whatever reads from it stops too.
+pub fn spawn(mut computer: Computer, input: Receiver<i64>, output: Sender<i64>) -> Handle {
+  computer.set_input_source(Box::new(input));
+
+  let thread = thread::spawn(move || {
+    let mut outputs = Vec::new();
+
+    let interruption = loop {
+      match computer.run() {
+        Interruption::Output => {
+          let value = computer
+            .output()
+            .expect("Failed to retrieve output from computer");
+          outputs.push(value);
+          // the reader may be gone already, e.g. the end of a feedback loop
+          output.send(value).ok();
+        }
+        interruption => break interruption,
+      }
+    };
+
+    computer.take_input_source();
+
+    Finished {
+      state: computer.get_state(),
+      computer,
+      outputs,
+      interruption,
+    }
+  });
+
+  Handle { thread }
+}
+
+// Connects the computers one after the other, each one's output feeding the
+// next one's input. Returns the input of the first computer and the output of
+// the last.
+pub fn chain(computers: Vec<Computer>) -> (Sender<i64>, Receiver<i64>, Vec<Handle>) {
+  let (first, mut input) = mpsc::channel();
+  let mut handles = Vec::with_capacity(computers.len());
+
+  for computer in computers {
+    let (output, next) = mpsc::channel();
+    handles.push(spawn(computer, input, output));
+    input = next;
+  }
+
+  (first, input, handles)
+}
+
+// Connects the computers in a feedback loop: like `chain`, with the last
+// computer's output going back into the first. Returns the first computer's
+// input, to get the loop going; drop it afterwards, or the first computer
+// will wait on it forever once the rest of the loop is done.
+pub fn ring(computers: Vec<Computer>) -> (Sender<i64>, Vec<Handle>) {
+  let (first, input) = mpsc::channel();
+  let mut input = Some(input);
+  let mut handles = Vec::with_capacity(computers.len());
+  let count = computers.len();
+
+  for (index, computer) in computers.into_iter().enumerate() {
+    let (output, next) = if index + 1 == count {
+      (first.clone(), None)
+    } else {
+      let (output, next) = mpsc::channel();
+      (output, Some(next))
+    };
+
+    handles.push(spawn(computer, input.take().unwrap(), output));
+    input = next;
+  }
+
+  (first, handles)
+}
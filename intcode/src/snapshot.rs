@@ -0,0 +1,247 @@
+use crate::computer::{Arithmetic, Engine, State, MEMORY_LIMIT};
+use crate::memory::Memory;
+use std::collections::VecDeque;
+use std::fmt;
+use std::fs;
+use std::io;
+
+const MAGIC: &str = "intcode-snapshot";
+const VERSION: u32 = 3;
+
+// The full state of a computer, as saved to disk. The format is plain text,
+// one field per line:
+//
+//   intcode-snapshot 3
+//   state Interrupted
+//   pc 12
+//   fp 1000
+//   executed 52143
+//   arithmetic Wrapping
+//   engine Cached
+//   input 1,2
+//   output
+//   high-water-mark 1034
+//   memory 0 109,1,21101,...
+//   memory 1024 7,0,...
+//
+// Only allocated pages are saved, one `memory` line each with its trailing
+// zeros left out. Older versions are still read, taking the defaults for the
+// fields they didn't have.
+#[derive(Debug, Clone)]
+pub struct Snapshot {
+  pub state: State,
+  pub pc: u64,
+  pub fp: i64,
+  pub executed: u64,
+  pub arithmetic: Arithmetic,
+  pub engine: Engine,
+  pub memory: Memory,
+  pub input: VecDeque<i64>,
+  pub output: VecDeque<i64>,
+}
+
+#[derive(Debug)]
+pub enum SnapshotError {
+  Io(io::Error),
+  UnsupportedVersion(u32),
+  Invalid { line: usize, message: String },
+}
+
+impl fmt::Display for SnapshotError {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    match self {
+      SnapshotError::Io(err) => write!(f, "{}", err),
+      SnapshotError::UnsupportedVersion(version) => {
+        write!(f, "unsupported snapshot version {}", version)
+      }
+      SnapshotError::Invalid { line, message } => write!(f, "line {}: {}", line, message),
+    }
+  }
+}
+
+impl From<io::Error> for SnapshotError {
+  fn from(err: io::Error) -> SnapshotError {
+    SnapshotError::Io(err)
+  }
+}
+
+fn invalid<T>(line: usize, message: String) -> Result<T, SnapshotError> {
+  Err(SnapshotError::Invalid { line, message })
+}
+
+fn out_of_range<T>(line: usize, addr: u64) -> Result<T, SnapshotError> {
+  invalid(line, format!("address {} is out of range", addr))
+}
+
+impl Snapshot {
+  pub fn save(&self, filename: &str) -> Result<(), SnapshotError> {
+    fs::write(filename, self.encode())?;
+    Ok(())
+  }
+
+  pub fn open(filename: &str) -> Result<Snapshot, SnapshotError> {
+    Snapshot::decode(&fs::read_to_string(filename)?)
+  }
+
+  pub fn encode(&self) -> String {
+    let mut lines = vec![
+      format!("{} {}", MAGIC, VERSION),
+      format!("state {:?}", self.state),
+      format!("pc {}", self.pc),
+      format!("fp {}", self.fp),
+      format!("executed {}", self.executed),
+      format!("arithmetic {:?}", self.arithmetic),
+      format!("engine {:?}", self.engine),
+      format!("input {}", join(self.input.iter())),
+      format!("output {}", join(self.output.iter())),
+      format!("high-water-mark {}", self.memory.high_water_mark()),
+    ];
+
+    for (base, words) in self.memory.pages() {
+      let used = words
+        .iter()
+        .rposition(|&w| w != 0)
+        .map_or(0, |last| last + 1);
+      lines.push(format!("memory {} {}", base, join(words[..used].iter())));
+    }
+
+    lines
+      .iter()
+      .map(|line| line.trim_end())
+      .collect::<Vec<&str>>()
+      .join("\n")
+      + "\n"
+  }
+
+  pub fn decode(text: &str) -> Result<Snapshot, SnapshotError> {
+    let mut lines = text.lines().enumerate().map(|(i, line)| (i + 1, line));
+
+    match lines.next() {
+      Some((number, header)) => {
+        let version = match header.strip_prefix(MAGIC) {
+          Some(version) => version.trim(),
+          None => return invalid(number, String::from("not an intcode snapshot")),
+        };
+        let version = parse(number, version)?;
+        if !(1..=VERSION).contains(&version) {
+          return Err(SnapshotError::UnsupportedVersion(version));
+        }
+      }
+      None => return invalid(1, String::from("empty snapshot")),
+    }
+
+    let mut snapshot = Snapshot {
+      state: State::Booting,
+      pc: 0,
+      fp: 0,
+      executed: 0,
+      arithmetic: Arithmetic::Wrapping,
+      engine: Engine::Interpreter,
+      memory: Memory::new(),
+      input: VecDeque::new(),
+      output: VecDeque::new(),
+    };
+    let mut high_water_mark = 0;
+
+    for (number, line) in lines {
+      let line = line.trim();
+      if line.is_empty() {
+        continue;
+      }
+
+      let (key, value) = match line.find(' ') {
+        Some(space) => (&line[..space], line[space + 1..].trim()),
+        None => (line, ""),
+      };
+
+      match key {
+        "state" => snapshot.state = parse_state(number, value)?,
+        "pc" => snapshot.pc = parse(number, value)?,
+        "fp" => snapshot.fp = parse(number, value)?,
+        "executed" => snapshot.executed = parse(number, value)?,
+        "arithmetic" => snapshot.arithmetic = parse_arithmetic(number, value)?,
+        "engine" => snapshot.engine = parse_engine(number, value)?,
+        "input" => snapshot.input = parse_list(number, value)?.into_iter().collect(),
+        "output" => snapshot.output = parse_list(number, value)?.into_iter().collect(),
+        "high-water-mark" => {
+          high_water_mark = parse(number, value)?;
+          if high_water_mark > MEMORY_LIMIT {
+            return out_of_range(number, high_water_mark - 1);
+          }
+        }
+        "memory" => {
+          let (base, words) = match value.find(' ') {
+            Some(space) => (&value[..space], &value[space + 1..]),
+            None => (value, ""),
+          };
+          let base: u64 = parse(number, base)?;
+
+          for (offset, word) in parse_list(number, words)?.into_iter().enumerate() {
+            match base.checked_add(offset as u64) {
+              Some(addr) if addr < MEMORY_LIMIT => snapshot.memory.write(addr, word),
+              _ => return out_of_range(number, base.saturating_add(offset as u64)),
+            }
+          }
+        }
+        _ => return invalid(number, format!("unknown field '{}'", key)),
+      }
+    }
+
+    // zeros written past the last non-zero word still count as written
+    if high_water_mark > snapshot.memory.high_water_mark() {
+      snapshot.memory.write(high_water_mark - 1, 0);
+    }
+
+    Ok(snapshot)
+  }
+}
+
+fn join<'a, I: Iterator<Item = &'a i64>>(values: I) -> String {
+  let values: Vec<String> = values.map(|v| v.to_string()).collect();
+  values.join(",")
+}
+
+fn parse<T: std::str::FromStr>(line: usize, text: &str) -> Result<T, SnapshotError> {
+  text
+    .parse()
+    .or_else(|_| invalid(line, format!("invalid number '{}'", text)))
+}
+
+fn parse_list(line: usize, text: &str) -> Result<Vec<i64>, SnapshotError> {
+  if text.trim().is_empty() {
+    return Ok(Vec::new());
+  }
+
+  text
+    .split(',')
+    .map(|value| parse(line, value.trim()))
+    .collect()
+}
+
+fn parse_state(line: usize, text: &str) -> Result<State, SnapshotError> {
+  match text {
+    "Booting" => Ok(State::Booting),
+    "Ready" => Ok(State::Ready),
+    "Running" => Ok(State::Running),
+    "Interrupted" => Ok(State::Interrupted),
+    "Halted" => Ok(State::Halted),
+    "Faulted" => Ok(State::Faulted),
+    _ => invalid(line, format!("invalid state '{}'", text)),
+  }
+}
+
+fn parse_arithmetic(line: usize, text: &str) -> Result<Arithmetic, SnapshotError> {
+  match text {
+    "Wrapping" => Ok(Arithmetic::Wrapping),
+    "Checked" => Ok(Arithmetic::Checked),
+    _ => invalid(line, format!("invalid arithmetic '{}'", text)),
+  }
+}
+
+fn parse_engine(line: usize, text: &str) -> Result<Engine, SnapshotError> {
+  match text {
+    "Interpreter" => Ok(Engine::Interpreter),
+    "Cached" => Ok(Engine::Cached),
+    _ => invalid(line, format!("invalid engine '{}'", text)),
+  }
+}
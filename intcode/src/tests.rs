@@ -0,0 +1,1519 @@
+use crate::{
+  analyze, assemble, chain, check_conformance, decompile, disassemble, format_program, is_ascii,
+  parse, replay, ring, Access, Actual, Arithmetic, Ascii, AsciiOutput, BigInt, Branch, Case,
+  Computer, Coverage, Debugger, Divergence, EdgeKind, Effect, Engine, Entry, Example, Exit, Fault,
+  Finished, FnInput, Fuzzer, Handle, Instruction, Interruption, IterInput, JsonTracer, Machine,
+  Modification, Network, NumberInput, Opcode, Optimizer, Outcome, PrettyTracer, Recorder, Snapshot,
+  SnapshotError, State, Topology, Transcript, TranscriptError, Word, PAGE_SIZE,
+};
+use std::env;
+use std::fs;
+use std::io;
+use std::io::Write;
+use std::sync::mpsc;
+use std::sync::{Arc, Mutex};
+
+fn run_to_halt(computer: &mut Computer) -> Vec<i64> {
+  let mut outputs = Vec::new();
+
+  loop {
+    match computer.run() {
+      Interruption::Output => outputs.push(computer.output().unwrap()),
+      Interruption::Input => panic!("computer is asking for input but none was given"),
+      Interruption::Halt => return outputs,
+      Interruption::Fault(fault) => panic!("computer faulted: {}", fault),
+      Interruption::BudgetExhausted => unreachable!(),
+    }
+  }
+}
+
+#[test]
+fn test_add_and_multiply() {
+  let mut computer = Computer::new();
+  computer.load("1,9,10,3,2,3,11,0,99,30,40,50");
+  run_to_halt(&mut computer);
+  assert_eq!(computer.read(0), 3500);
+}
+
+#[test]
+fn test_input_interruption() {
+  let mut computer = Computer::new();
+  computer.load("3,9,8,9,10,9,4,9,99,-1,8");
+
+  match computer.run() {
+    Interruption::Input => (),
+    _ => panic!("expected an input interruption"),
+  }
+
+  computer.input(8);
+  assert_eq!(run_to_halt(&mut computer), vec![1]);
+}
+
+#[test]
+fn test_relative_base_quine() {
+  let program = "109,1,204,-1,1001,100,1,100,1008,100,16,101,1006,101,0,99";
+  let mut computer = Computer::new();
+  computer.load(program);
+
+  let outputs: Vec<String> = run_to_halt(&mut computer)
+    .iter()
+    .map(|n| n.to_string())
+    .collect();
+  assert_eq!(outputs.join(","), program);
+}
+
+#[test]
+fn test_fault_unknown_opcode() {
+  let mut computer = Computer::new();
+  computer.load("1101,1,2,5,42,0");
+
+  match computer.run() {
+    Interruption::Fault(fault) => {
+      assert_eq!(
+        fault,
+        Fault::UnknownOpcode {
+          pc: 4,
+          fp: 0,
+          instruction: 42
+        }
+      );
+    }
+    _ => panic!("expected a fault"),
+  }
+
+  match computer.get_state() {
+    State::Faulted => (),
+    state => panic!("expected faulted state, got {:?}", state),
+  }
+}
+
+#[test]
+fn test_fault_invalid_parameter_mode() {
+  let mut computer = Computer::new();
+  computer.load("301,0,0,0,99");
+
+  match computer.run() {
+    Interruption::Fault(Fault::InvalidParameterMode {
+      mode, instruction, ..
+    }) => {
+      assert_eq!(mode, 3);
+      assert_eq!(instruction, 301);
+    }
+    _ => panic!("expected an invalid parameter mode fault"),
+  }
+}
+
+#[test]
+fn test_fault_write_in_immediate_mode() {
+  let mut computer = Computer::new();
+  computer.load("11101,1,2,3,99");
+
+  match computer.run() {
+    Interruption::Fault(Fault::WriteInImmediateMode { pc, .. }) => assert_eq!(pc, 0),
+    _ => panic!("expected a write in immediate mode fault"),
+  }
+}
+
+#[test]
+fn test_fault_negative_address() {
+  let mut computer = Computer::new();
+  computer.load("109,-10,204,0,99");
+
+  match computer.run() {
+    Interruption::Fault(Fault::NegativeAddress {
+      pc, fp, address, ..
+    }) => {
+      assert_eq!(pc, 2);
+      assert_eq!(fp, -10);
+      assert_eq!(address, -10);
+    }
+    _ => panic!("expected a negative address fault"),
+  }
+}
+
+#[test]
+fn test_fault_address_out_of_range() {
+  let mut computer = Computer::new();
+  computer.load("4,2000000000,99");
+
+  match computer.run() {
+    Interruption::Fault(Fault::AddressOutOfRange { address, .. }) => {
+      assert_eq!(address, 2000000000)
+    }
+    _ => panic!("expected an address out of range fault"),
+  }
+}
+
+#[test]
+fn test_memory_grows_on_demand() {
+  let mut computer = Computer::new();
+  computer.load("1101,20,22,100000,4,100000,99");
+
+  assert_eq!(run_to_halt(&mut computer), vec![42]);
+  assert_eq!(computer.memory().high_water_mark(), 100001);
+  assert_eq!(computer.memory().allocated_pages(), 2);
+}
+
+#[test]
+fn test_host_writes_are_sparse_and_limited() {
+  let mut computer = Computer::new();
+  computer.load("99");
+
+  computer
+    .write((1 << 30) - 1, 7)
+    .expect("Failed to write below the limit");
+  assert_eq!(computer.read((1 << 30) - 1), 7);
+  assert_eq!(computer.memory().allocated_pages(), 2);
+
+  match computer.write(1 << 30, 7) {
+    Err(Fault::AddressOutOfRange { address, .. }) => assert_eq!(address, 1 << 30),
+    result => panic!("expected an address out of range fault, got {:?}", result),
+  }
+  assert_eq!(computer.memory().allocated_pages(), 2);
+}
+
+#[test]
+fn test_memory_is_shared_between_clones() {
+  let mut original = Computer::new();
+  original.load("1101,20,22,10,99");
+  original
+    .write(PAGE_SIZE as u64 * 3, 7)
+    .expect("Failed to write");
+
+  let mut clone = original.clone();
+  assert_eq!(clone.memory().shared_pages(), 2);
+
+  assert_eq!(run_to_halt(&mut clone), vec![]);
+  assert_eq!(clone.read(10), 42);
+  assert_eq!(original.read(10), 0);
+
+  // the untouched page is still shared, the program page was copied
+  assert_eq!(original.memory().shared_pages(), 1);
+  assert_eq!(clone.memory().shared_pages(), 1);
+}
+
+#[test]
+fn test_instruction_decode_and_encode() {
+  let instruction = Instruction::decode(&[21101, 3, 5, 100]).unwrap();
+  assert_eq!(instruction.to_string(), "add #3, #5 -> [rb+100]");
+  assert_eq!(instruction.encode(), vec![21101, 3, 5, 100]);
+
+  let instruction = Instruction::decode(&[1201, -3, 5, 100]).unwrap();
+  assert_eq!(instruction.to_string(), "add [rb-3], #5 -> [100]");
+
+  // write parameter in immediate mode, bad mode, truncated instruction
+  assert_eq!(Instruction::decode(&[11101, 3, 5, 100]), None);
+  assert_eq!(Instruction::decode(&[301, 3, 5, 100]), None);
+  assert_eq!(Instruction::decode(&[1, 3, 5]), None);
+}
+
+#[test]
+fn test_disassemble_labels_and_data() {
+  let program = parse("3,12,1006,12,10,104,1,1105,1,11,99,42,-1");
+  let listing = disassemble(&program);
+
+  assert_eq!(
+    listing.to_string(),
+    [
+      "  in -> [12]                            ; 0: 3,12",
+      "  jf [12], #L10                         ; 2: 1006,12,10",
+      "  out #1                                ; 5: 104,1",
+      "  jt #1, #L11                           ; 7: 1105,1,11",
+      "L10:",
+      "  hlt                                   ; 10: 99",
+      "L11:",
+      "  data 42, -1                           ; 11: 42,-1",
+      "",
+    ]
+    .join("\n")
+  );
+}
+
+#[test]
+fn test_assemble_round_trips_disassembly() {
+  let program = parse(&fs::read_to_string("../day09/input.txt").unwrap());
+  let listing = disassemble(&program).to_string();
+
+  assert_eq!(assemble(&listing).unwrap(), program);
+}
+
+#[test]
+fn test_assemble_labels_macros_and_data() {
+  let source = "
+    ; counts down from 3, printing every value
+    macro push value
+      add value, #0 -> [fp+0]
+      arb #1
+    endm
+
+    macro pop target
+      arb #-1
+      add [fp+0], #0 -> target
+    endm
+
+      arb #stack
+      push [counter]
+    loop:
+      pop [counter]
+      out [counter]
+      add [counter], #-1 -> [counter]
+      push [counter]
+      jt [counter], #loop
+      hlt
+    counter: data 3
+    stack: space 2
+  ";
+
+  let words = assemble(source).unwrap();
+  let mut computer = Computer::new();
+  computer.load(&format_program(&words));
+
+  assert_eq!(run_to_halt(&mut computer), vec![3, 2, 1]);
+}
+
+#[test]
+fn test_assemble_errors() {
+  let err = assemble("add #1, #2 -> #3").unwrap_err();
+  assert_eq!(err.line, 1);
+
+  let err = assemble("\njt #1, #nowhere").unwrap_err();
+  assert_eq!(err.to_string(), "line 2: undefined label 'nowhere'");
+
+  let err = assemble("mov #1, [2]").unwrap_err();
+  assert_eq!(err.to_string(), "line 1: unknown mnemonic 'mov'");
+
+  let err = assemble("data 9223372036854775807 + 1").unwrap_err();
+  assert_eq!(
+    err.to_string(),
+    "line 1: '9223372036854775807 + 1' is out of range"
+  );
+
+  let err = assemble("data 9223372036854775808").unwrap_err();
+  assert_eq!(
+    err.to_string(),
+    "line 1: '9223372036854775808' is out of range"
+  );
+
+  assert_eq!(
+    assemble("data -9223372036854775808, -1 - 9223372036854775807").unwrap(),
+    vec![i64::MIN, i64::MIN]
+  );
+
+  let err = assemble("data 1 2").unwrap_err();
+  assert_eq!(err.to_string(), "line 1: missing operator before '2'");
+
+  let err = assemble("data 5-").unwrap_err();
+  assert_eq!(err.to_string(), "line 1: missing operand in '5-'");
+
+  let err = assemble("data 1\nspace 1073741823").unwrap_err();
+  assert_eq!(
+    err.to_string(),
+    "line 2: space 1073741823 runs past the end of memory"
+  );
+  assert_eq!(assemble("space 3").unwrap(), vec![0, 0, 0]);
+}
+
+#[test]
+fn test_debugger_breakpoints_and_stepping() {
+  let mut computer = Computer::new();
+  computer.load("3,11,1001,11,1,11,4,11,1105,1,0,0");
+  let mut debugger = Debugger::new(computer);
+
+  debugger.execute("break 6");
+  assert_eq!(
+    debugger.execute("continue").unwrap(),
+    "waiting for input, queue values with `input <v>...`\n=> 0: in -> [11]"
+  );
+
+  debugger.execute("input 41");
+  assert_eq!(
+    debugger.execute("continue").unwrap(),
+    "breakpoint at 6\n=> 6: out [11]"
+  );
+  assert_eq!(debugger.execute("mem 11 1").unwrap(), "11: 42");
+
+  debugger.execute("set 11 7");
+  assert_eq!(
+    debugger.execute("step").unwrap(),
+    "output: 7\n=> 8: jt #1, #0"
+  );
+  assert_eq!(debugger.execute("io").unwrap(), "input: []\noutput: [7]");
+
+  assert_eq!(
+    debugger.execute("set 1073741824 1").unwrap(),
+    "address 1073741824 is out of range"
+  );
+  assert_eq!(
+    debugger.execute("mem 18446744073709551615").unwrap(),
+    "address 18446744073709551615 is out of range"
+  );
+  assert_eq!(
+    debugger
+      .execute("mem 1073741822 9223372036854775807")
+      .unwrap(),
+    "1073741822: 0 0"
+  );
+  assert_eq!(
+    debugger.execute("disasm 1073741823 4").unwrap(),
+    "   1073741823: data 0"
+  );
+  assert_eq!(
+    debugger.execute("mem 0 100000").unwrap().lines().count(),
+    128
+  );
+
+  debugger.execute("delete 6");
+  debugger.execute("break op in");
+  assert_eq!(
+    debugger.execute("c").unwrap(),
+    "breakpoint at 0\n=> 0: in -> [11]"
+  );
+  assert_eq!(debugger.execute("quit"), None);
+
+  // a program that never stops hands control back instead of hanging
+  let mut computer = Computer::new();
+  computer.load("1105,1,0");
+  let mut debugger = Debugger::new(computer);
+  assert_eq!(
+    debugger.execute("continue").unwrap(),
+    "still running after 10000000 instructions, `continue` to keep going\n=> 0: jt #1, #0"
+  );
+}
+
+#[derive(Clone, Default)]
+struct SharedBuffer(Arc<Mutex<Vec<u8>>>);
+
+impl Write for SharedBuffer {
+  fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+    self.0.lock().unwrap().write(buf)
+  }
+
+  fn flush(&mut self) -> io::Result<()> {
+    Ok(())
+  }
+}
+
+impl SharedBuffer {
+  fn contents(&self) -> String {
+    String::from_utf8(self.0.lock().unwrap().clone()).unwrap()
+  }
+}
+
+#[test]
+fn test_pretty_tracer() {
+  let buffer = SharedBuffer::default();
+  let mut computer = Computer::new();
+  computer.load("109,5,21101,3,4,0,204,0,99");
+  computer.set_tracer(Box::new(PrettyTracer::new(buffer.clone())));
+
+  assert_eq!(run_to_halt(&mut computer), vec![7]);
+  assert_eq!(
+    buffer.contents(),
+    [
+      "     0: arb #5                           ; #5",
+      "     2: add #3, #4 -> [rb+0]             ; #3, #4 -> [rb+0]=7",
+      "     6: out [rb+0]                       ; [rb+0]=7",
+      "     8: hlt                              ;",
+      "",
+    ]
+    .join("\n")
+  );
+}
+
+#[test]
+fn test_json_tracer() {
+  let buffer = SharedBuffer::default();
+  let mut computer = Computer::new();
+  computer.load("3,7,1001,7,-1,7,99,0");
+  computer.set_tracer(Box::new(JsonTracer::new(buffer.clone())));
+  computer.input(10);
+
+  run_to_halt(&mut computer);
+  let lines: Vec<String> = buffer.contents().lines().map(String::from).collect();
+
+  assert_eq!(lines.len(), 3);
+  assert_eq!(
+    lines[1],
+    "{\"pc\":2,\"fp\":0,\"opcode\":\"add\",\
+     \"operands\":[{\"mode\":0,\"value\":7},{\"mode\":1,\"value\":-1},{\"mode\":0,\"value\":7}],\
+     \"addresses\":[7,null,7],\"values\":[10,-1,10],\"write\":{\"addr\":7,\"value\":9}}"
+  );
+}
+
+#[test]
+fn test_tracers_show_faults_and_plugins() {
+  let buffer = SharedBuffer::default();
+  let mut computer = Computer::new();
+  computer.plugins_mut().register(
+    11,
+    "pow",
+    &[Access::Read, Access::Read, Access::Write],
+    |values: &[i64]| Effect::Store(values[0].pow(values[1] as u32)),
+  );
+  computer.load("1111,2,10,7,12,0,99");
+  computer.set_tracer(Box::new(PrettyTracer::new(buffer.clone())));
+
+  assert!(matches!(computer.run(), Interruption::Fault(_)));
+  assert_eq!(
+    buffer.contents(),
+    [
+      "     0: pow #2, #10 -> [7]               ; #2, #10 -> [7]=1024",
+      "     4: data 12                          ; fault: unknown opcode 12 (pc: 4, fp: 0, instruction: 12)",
+      "",
+    ]
+    .join("\n")
+  );
+
+  let buffer = SharedBuffer::default();
+  let mut computer = Computer::new();
+  computer.load("1,-1,0,0");
+  computer.set_tracer(Box::new(JsonTracer::new(buffer.clone())));
+
+  assert!(matches!(computer.run(), Interruption::Fault(_)));
+  assert_eq!(
+    buffer.contents(),
+    "{\"pc\":0,\"fp\":0,\"opcode\":\"add\",\
+     \"operands\":[{\"mode\":0,\"value\":-1},{\"mode\":0,\"value\":0},{\"mode\":0,\"value\":0}],\
+     \"addresses\":[null,0,0],\"values\":[-1,1,1],\"write\":null,\
+     \"fault\":\"negative address -1 (pc: 0, fp: 0, instruction: 1)\"}\n"
+  );
+
+  // a store that faults writes nothing
+  let buffer = SharedBuffer::default();
+  let mut computer = Computer::new();
+  computer.load("1101,1,2,1073741824,99");
+  computer.set_tracer(Box::new(JsonTracer::new(buffer.clone())));
+
+  assert!(matches!(computer.run(), Interruption::Fault(_)));
+  assert_eq!(
+    buffer.contents(),
+    "{\"pc\":0,\"fp\":0,\"opcode\":\"add\",\
+     \"operands\":[{\"mode\":1,\"value\":1},{\"mode\":1,\"value\":2},{\"mode\":0,\"value\":1073741824}],\
+     \"addresses\":[null,null,1073741824],\"values\":[1,2,0],\"write\":null,\
+     \"fault\":\"address 1073741824 out of range (pc: 0, fp: 0, instruction: 1101)\"}\n"
+  );
+}
+
+#[test]
+fn test_snapshot_round_trip() {
+  // echoes every input doubled, with a scratch word far past the program
+  let program = "3,100,1002,100,2,5000,4,5000,1105,1,0";
+  let mut computer = Computer::new();
+  computer.load(program);
+  computer.set_arithmetic(Arithmetic::Checked);
+  computer.set_engine(Engine::Cached);
+  computer.input(4);
+
+  assert!(matches!(computer.run(), Interruption::Output));
+  assert!(matches!(computer.run(), Interruption::Input));
+  computer.input(7);
+
+  let filename = env::temp_dir().join("intcode-snapshot-test.txt");
+  let filename = filename.to_str().unwrap();
+  computer.save(filename).unwrap();
+  let mut restored = Computer::restore(filename).unwrap();
+  fs::remove_file(filename).unwrap();
+
+  assert_eq!(restored.pc(), computer.pc());
+  assert_eq!(restored.pending_input(), computer.pending_input());
+  assert_eq!(restored.pending_output(), computer.pending_output());
+  assert_eq!(
+    restored.memory().high_water_mark(),
+    computer.memory().high_water_mark()
+  );
+  assert_eq!(restored.memory().allocated_pages(), 2);
+  assert_eq!(restored.arithmetic(), Arithmetic::Checked);
+  assert_eq!(restored.engine(), Engine::Cached);
+
+  assert!(matches!(restored.run(), Interruption::Output));
+  assert_eq!(restored.output(), Some(8));
+  assert_eq!(restored.output(), Some(14));
+}
+
+#[test]
+fn test_snapshot_errors() {
+  assert!(matches!(
+    Snapshot::decode("intcode-snapshot 4\n"),
+    Err(SnapshotError::UnsupportedVersion(4))
+  ));
+  // version 1 predates the engine field, and version 2 the arithmetic mode
+  let snapshot = Snapshot::decode("intcode-snapshot 1\npc 4\n").unwrap();
+  assert_eq!(snapshot.pc, 4);
+  assert_eq!(snapshot.engine, Engine::Interpreter);
+  let snapshot = Snapshot::decode("intcode-snapshot 2\nengine Cached\n").unwrap();
+  assert_eq!(snapshot.arithmetic, Arithmetic::Wrapping);
+  assert_eq!(snapshot.engine, Engine::Cached);
+  assert!(matches!(
+    Snapshot::decode("intcode-snapshot 2\nengine Jit\n"),
+    Err(SnapshotError::Invalid { line: 2, .. })
+  ));
+  assert!(matches!(
+    Snapshot::decode("intcode-snapshot 1\npc twelve\n"),
+    Err(SnapshotError::Invalid { line: 2, .. })
+  ));
+  assert!(matches!(
+    Snapshot::decode("1,2,3\n"),
+    Err(SnapshotError::Invalid { line: 1, .. })
+  ));
+
+  for text in [
+    "memory 1073741824 1",
+    "memory 1073741823 1,2",
+    "memory 18446744073709551615 1,2",
+    "high-water-mark 1073741825",
+  ]
+  .iter()
+  {
+    match Snapshot::decode(&format!("intcode-snapshot 1\n{}\n", text)) {
+      Err(SnapshotError::Invalid { line: 2, message }) => assert!(message.contains("out of range")),
+      result => panic!("expected {} to be out of range, got {:?}", text, result),
+    }
+  }
+  assert!(Snapshot::decode("intcode-snapshot 1\nmemory 1073741823 1\n").is_ok());
+}
+
+#[test]
+fn test_run_for_budget() {
+  let mut computer = Computer::new();
+  computer.load("1105,1,0");
+
+  assert!(matches!(
+    computer.run_for(1000),
+    Interruption::BudgetExhausted
+  ));
+  assert_eq!(computer.executed(), 1000);
+  assert!(matches!(computer.get_state(), State::Interrupted));
+
+  let mut computer = Computer::new();
+  computer.load("3,9,1001,9,1,9,4,9,99,0");
+  computer.input(41);
+
+  assert!(matches!(computer.run_for(2), Interruption::BudgetExhausted));
+  assert!(matches!(computer.run_for(2), Interruption::Output));
+  assert_eq!(computer.output(), Some(42));
+  assert!(matches!(computer.run_for(10), Interruption::Halt));
+  assert_eq!(computer.executed(), 4);
+}
+
+#[test]
+fn test_executed_skips_input_wait() {
+  let mut computer = Computer::new();
+  computer.load("3,0,99");
+
+  assert!(matches!(computer.run(), Interruption::Input));
+  assert_eq!(computer.executed(), 0);
+
+  computer.input(1);
+  assert!(matches!(computer.run(), Interruption::Halt));
+  assert_eq!(computer.executed(), 2);
+}
+
+#[test]
+fn test_cached_engine_self_modifying() {
+  // `out #0` at address 0 has its operand incremented until it reaches 3
+  let program = "104,0,1001,1,1,1,1007,1,3,20,1005,20,0,99";
+
+  for &engine in [Engine::Interpreter, Engine::Cached].iter() {
+    let mut computer = Computer::new();
+    computer.set_engine(engine);
+    computer.load(program);
+
+    assert_eq!(run_to_halt(&mut computer), vec![0, 1, 2]);
+  }
+}
+
+#[test]
+fn test_cached_engine_matches_interpreter() {
+  let boost = fs::read_to_string("../day09/input.txt").unwrap();
+  let programs = [boost.as_str(), "1101,1,2,5,42,0", "3,0,99", "1,0,0,-1,99"];
+
+  for program in programs.iter() {
+    let run = |engine| {
+      let mut computer = Computer::new();
+      computer.set_engine(engine);
+      computer.load(program);
+      computer.input(1);
+      let mut outputs = Vec::new();
+      let fault = loop {
+        match computer.run() {
+          Interruption::Output => outputs.push(computer.output().unwrap()),
+          Interruption::Fault(fault) => break Some(fault),
+          _ => break None,
+        }
+      };
+      (outputs, fault, computer.pc(), computer.executed())
+    };
+
+    assert_eq!(run(Engine::Cached), run(Engine::Interpreter));
+  }
+}
+
+#[test]
+fn test_profiler() {
+  // counts down from 3, looping back to address 0
+  let program = "1001,9,-1,9,1005,9,0,99,0,3";
+  let mut computer = Computer::new();
+  computer.load(program);
+  computer.start_profiling();
+  run_to_halt(&mut computer);
+
+  let profile = computer.stop_profiling().unwrap();
+  assert_eq!(profile.total(), 7);
+  assert_eq!(profile.count(0), 3);
+  assert_eq!(profile.count(7), 1);
+  assert_eq!(
+    profile.opcode_histogram(),
+    vec![(Opcode::Add, 3), (Opcode::JumpIfTrue, 3), (Opcode::Halt, 1)]
+  );
+  assert_eq!(profile.back_edges(), vec![((4, 0), 2)]);
+  assert!(profile
+    .to_csv()
+    .lines()
+    .any(|line| line == "back-edge,4,0,jt,2"));
+}
+
+#[test]
+fn test_coverage() {
+  let program = "3,11,1005,11,8,104,1,99,104,2,99,0";
+  let run = |input, coverage| {
+    let mut computer = Computer::new();
+    computer.load(program);
+    computer.input(input);
+    computer.start_coverage(coverage);
+    run_to_halt(&mut computer);
+    computer.stop_coverage().unwrap()
+  };
+
+  let coverage = run(0, Coverage::new());
+  assert!(coverage.executed(5) && !coverage.executed(8));
+  assert!(coverage.is_code(6) && !coverage.is_code(9));
+  assert!(coverage.was_read(11) && coverage.was_written(11));
+  assert_eq!(
+    coverage.branch(2),
+    Some(Branch {
+      taken: false,
+      not_taken: true
+    })
+  );
+
+  let coverage = run(1, coverage);
+  assert!(coverage.executed(5) && coverage.executed(8));
+  assert_eq!(
+    coverage.branch(2),
+    Some(Branch {
+      taken: true,
+      not_taken: true
+    })
+  );
+
+  let listing = coverage.annotate(&parse(program));
+  let lines: Vec<&str> = listing.lines().collect();
+  assert_eq!(
+    lines[0],
+    "; 6 of 6 instructions executed (100.0%), 1 of 1 executed branches went both ways"
+  );
+  assert!(lines[1].starts_with("+     in -> [11]"));
+  assert!(lines.last().unwrap().starts_with(" rw   data 0"));
+}
+
+#[test]
+fn test_record_and_replay() {
+  // outputs each input plus one until it reads a zero
+  let program = "3,15,1006,15,14,101,1,15,15,4,15,1105,1,0,99,0";
+
+  let buffer = SharedBuffer::default();
+  let mut computer = Computer::new();
+  computer.load(program);
+  computer.set_recorder(Recorder::new(Box::new(buffer.clone())));
+  for &value in [5, 9].iter() {
+    computer.input(value);
+    assert!(matches!(computer.run(), Interruption::Output));
+    computer.output();
+  }
+  drop(computer);
+
+  let transcript = Transcript::decode(&buffer.contents()).unwrap();
+  assert_eq!(
+    transcript.entries,
+    vec![
+      Entry::Input {
+        value: 5,
+        executed: 0
+      },
+      Entry::Output {
+        value: 6,
+        executed: 4
+      },
+      Entry::Input {
+        value: 9,
+        executed: 4
+      },
+      Entry::Output {
+        value: 10,
+        executed: 9
+      },
+    ]
+  );
+  assert_eq!(
+    Transcript::decode(&transcript.encode()).unwrap(),
+    transcript
+  );
+
+  let mut computer = Computer::new();
+  computer.load(program);
+  assert_eq!(replay(&mut computer, &transcript), Ok(4));
+
+  let mut tampered = transcript.clone();
+  tampered.entries[3] = Entry::Output {
+    value: 11,
+    executed: 9,
+  };
+  let mut computer = Computer::new();
+  computer.load(program);
+  assert_eq!(
+    replay(&mut computer, &tampered),
+    Err(Divergence {
+      entry: 3,
+      expected: 11,
+      recorded_at: 9,
+      actual: Actual::Output(10),
+      executed: 9
+    })
+  );
+}
+
+#[test]
+fn test_record_and_replay_patches() {
+  let program = "104,0,3,7,4,7,99,0";
+
+  let buffer = SharedBuffer::default();
+  let mut computer = Computer::new();
+  computer.load(program);
+  computer.set_recorder(Recorder::new(Box::new(buffer.clone())));
+  computer.write(1, 42).expect("Failed to patch");
+  computer.input(3);
+  assert_eq!(run_to_halt(&mut computer), vec![42, 3]);
+
+  // entries are flushed as they're recorded
+  let transcript = Transcript::decode(&buffer.contents()).unwrap();
+  assert_eq!(
+    transcript.entries[0],
+    Entry::Write {
+      address: 1,
+      value: 42,
+      executed: 0
+    }
+  );
+  assert!(transcript.encode().contains("\nwrite 1 42 0\n"));
+
+  let mut computer = Computer::new();
+  computer.load(program);
+  assert_eq!(replay(&mut computer, &transcript), Ok(4));
+
+  assert!(matches!(
+    Transcript::decode("intcode-transcript 2\nwrite 1073741824 1 0\n"),
+    Err(TranscriptError::Invalid { line: 2, .. })
+  ));
+}
+
+#[test]
+fn test_ascii_io() {
+  let program = assemble(
+    "
+      out #72
+      out #105
+      out #10
+      out #63
+    loop:
+      in -> [char]
+      out [char]
+      eq [char], #10 -> [done]
+      jf [done], #loop
+      out #1000
+      hlt
+    char: data 0
+    done: data 0
+    ",
+  )
+  .unwrap();
+
+  let mut computer = Computer::new();
+  computer.load(&format_program(&program));
+
+  assert_eq!(
+    computer.read_line().unwrap(),
+    Ascii::Text(String::from("Hi"))
+  );
+  assert_eq!(computer.read_until_prompt().unwrap(), "?");
+  computer.send_line("ok");
+  assert_eq!(
+    computer.read_line().unwrap(),
+    Ascii::Text(String::from("ok"))
+  );
+  assert_eq!(computer.read_line().unwrap(), Ascii::Value(1000));
+  assert!(matches!(computer.read_line(), Err(Interruption::Halt)));
+  assert!(is_ascii(10) && !is_ascii(1000) && !is_ascii(-1));
+}
+
+#[test]
+fn test_iterator_input_and_channel_output() {
+  // outputs 1 if the input is 8, 0 otherwise
+  let program = "3,9,8,9,10,9,4,9,99,-1,8";
+  let (sender, receiver) = mpsc::channel();
+
+  let mut computer = Computer::new();
+  computer.load(program);
+  computer.set_input_source(Box::new(IterInput(vec![8].into_iter())));
+  computer.set_output_sink(Box::new(sender));
+
+  assert!(matches!(computer.run(), Interruption::Halt));
+  assert_eq!(receiver.try_iter().collect::<Vec<i64>>(), vec![1]);
+  assert!(computer.pending_output().is_empty());
+}
+
+#[test]
+fn test_reader_input_and_writer_output() {
+  let program = assemble(
+    "
+      in -> [a]
+      in -> [b]
+      in -> [c]
+      add [a], [b] -> [a]
+      add [a], [c] -> [a]
+      out #79
+      out #75
+      out #10
+      out [a]
+      hlt
+    a: data 0
+    b: data 0
+    c: data 0
+    ",
+  )
+  .unwrap();
+  let buffer = SharedBuffer::default();
+
+  let mut computer = Computer::new();
+  computer.load(&format_program(&program));
+  computer.set_input_source(Box::new(NumberInput::new(io::Cursor::new(
+    "300, 400\n500\n",
+  ))));
+  computer.set_output_sink(Box::new(AsciiOutput(buffer.clone())));
+
+  assert!(matches!(computer.run(), Interruption::Halt));
+  assert_eq!(buffer.contents(), "OK\n1200\n");
+}
+
+#[test]
+fn test_closure_input_runs_dry() {
+  let mut outputs = Vec::new();
+  let mut remaining = 2;
+
+  let mut computer = Computer::new();
+  computer.load("3,7,4,7,1105,1,0,0");
+  computer.set_input_source(Box::new(FnInput(move || {
+    remaining -= 1;
+    if remaining >= 0 {
+      Some(remaining)
+    } else {
+      None
+    }
+  })));
+
+  loop {
+    match computer.run() {
+      Interruption::Output => outputs.push(computer.output().unwrap()),
+      Interruption::Input => break,
+      interruption => panic!("unexpected interruption: {:?}", interruption),
+    }
+  }
+  assert_eq!(outputs, vec![1, 0]);
+
+  computer.input(7);
+  assert!(matches!(computer.run(), Interruption::Output));
+  assert_eq!(computer.output(), Some(7));
+}
+
+#[test]
+fn test_chain_runner() {
+  // each computer adds its own offset to every input
+  let computers = (1..=3)
+    .map(|offset| {
+      let mut computer = Computer::new();
+      computer.load(&format!("3,11,101,{},11,11,4,11,1105,1,0,0", offset * 10));
+      computer
+    })
+    .collect();
+
+  let (input, output, handles) = chain(computers);
+  input.send(1).unwrap();
+  input.send(2).unwrap();
+  drop(input);
+
+  assert_eq!(output.iter().collect::<Vec<i64>>(), vec![61, 62]);
+  for handle in handles {
+    let finished = handle.join();
+    assert!(matches!(finished.interruption, Interruption::Input));
+    assert_eq!(finished.outputs.len(), 2);
+  }
+}
+
+#[test]
+fn test_ring_runner() {
+  // the day 7 feedback loop example
+  let program = "3,26,1001,26,-4,26,3,27,1002,27,2,27,1,27,26,27,4,27,1001,28,-1,28,\
+                 1005,28,6,99,0,0,5";
+  let computers = [9, 8, 7, 6, 5]
+    .iter()
+    .map(|&phase| {
+      let mut computer = Computer::new();
+      computer.load(program);
+      computer.input(phase);
+      computer
+    })
+    .collect();
+
+  let (input, handles) = ring(computers);
+  input.send(0).unwrap();
+  drop(input);
+
+  let finished: Vec<Finished> = handles.into_iter().map(Handle::join).collect();
+  assert!(finished
+    .iter()
+    .all(|finished| matches!(finished.state, State::Halted)));
+  assert_eq!(finished[4].outputs.last(), Some(&139629729));
+}
+
+#[test]
+fn test_network_ring_with_tap() {
+  let program = "3,26,1001,26,-4,26,3,27,1002,27,2,27,1,27,26,27,4,27,1001,28,-1,28,\
+                 1005,28,6,99,0,0,5";
+  let names = ["a", "b", "c", "d", "e"];
+
+  let mut network = Network::new();
+  for (name, &phase) in names.iter().zip([9, 8, 7, 6, 5].iter()) {
+    let mut computer = Computer::new();
+    computer.load(program);
+    network.add_node(name, computer, &[phase]);
+  }
+  for pair in names.windows(2) {
+    network.connect(pair[0], pair[1]);
+  }
+  network.connect("e", "a");
+  network.add_output("e", "thrusters");
+  network.input("a", 0);
+
+  let report = network.run();
+  assert_eq!(report.outcome, Outcome::Halted);
+  assert!(report.faults.is_empty());
+  assert_eq!(report.outputs["thrusters"].last(), Some(&139629729));
+}
+
+#[test]
+fn test_network_fan_out_and_fan_in() {
+  // "add" adds its offset to every input, "sum" keeps a running total
+  let add = |offset| {
+    let mut computer = Computer::new();
+    computer.load(&format!("3,11,101,{},11,11,4,11,1105,1,0,0", offset));
+    computer
+  };
+  let mut sum = Computer::new();
+  sum.load("3,11,1,11,12,12,4,12,1105,1,0,0,0");
+
+  let mut network = Network::new();
+  network.add_node("source", add(0), &[]);
+  network.add_node("ten", add(10), &[]);
+  network.add_node("hundred", add(100), &[]);
+  network.add_node("sum", sum, &[]);
+  network.connect("source", "ten");
+  network.connect("source", "hundred");
+  network.connect("ten", "sum");
+  network.connect("hundred", "sum");
+  network.add_output("sum", "total");
+  network.add_output("ten", "tens");
+  network.input("source", 1);
+  network.input("source", 2);
+
+  let report = network.run();
+  assert_eq!(report.outcome, Outcome::Quiescent);
+  assert_eq!(report.outputs["tens"], vec![11, 12]);
+  assert_eq!(report.outputs["total"], vec![11, 23, 124, 226]);
+
+  // the network can be fed more input once it has settled
+  network.input("source", 3);
+  let report = network.run();
+  assert_eq!(report.outputs["total"], vec![239, 342]);
+}
+
+#[test]
+fn test_network_budget() {
+  // a node that spins forever, and one that's done at once
+  let mut spinner = Computer::new();
+  spinner.load("1105,1,0");
+  let mut halter = Computer::new();
+  halter.load("99");
+
+  let mut network = Network::new();
+  network.add_node("spinner", spinner, &[]);
+  network.add_node("halter", halter, &[]);
+  network.set_budget(25_000);
+
+  let report = network.run();
+  assert_eq!(report.outcome, Outcome::BudgetExhausted);
+  assert_eq!(network.computer("spinner").executed(), 25_000 - 1);
+
+  // the budget is per run
+  network.run();
+  assert_eq!(network.computer("spinner").executed(), 50_000 - 1);
+}
+
+#[test]
+fn test_phase_optimizer() {
+  let mut computer = Computer::new();
+  computer.load("3,15,3,16,1002,16,10,16,1,16,15,15,4,15,99,0,0");
+
+  let mut optimizer = Optimizer::new(computer, Topology::Chain, |outputs: &[i64]| {
+    outputs.last().copied()
+  });
+  optimizer.set_workers(3);
+
+  let best = optimizer.best(&[0, 1, 2, 3, 4]).unwrap();
+  assert_eq!(best.phases, vec![4, 3, 2, 1, 0]);
+  assert_eq!(best.score, 43210);
+
+  let ranked = optimizer.ranked(&[0, 1, 2, 3, 4]);
+  assert_eq!(ranked.len(), 120);
+  assert_eq!(ranked[0], best);
+  assert!(ranked.windows(2).all(|pair| pair[0].score >= pair[1].score));
+
+  // every ordering comes up once, however often a phase is repeated
+  let ranked = optimizer.ranked(&[0, 1, 2, 2, 3, 4, 0]);
+  assert_eq!(ranked.len(), 120);
+  let mut phases: Vec<Vec<i64>> = ranked.into_iter().map(|c| c.phases).collect();
+  phases.sort();
+  phases.dedup();
+  assert_eq!(phases.len(), 120);
+
+  // no phases means no amplifiers and nothing to score
+  assert_eq!(optimizer.best(&[]), None);
+  assert!(optimizer.ranked(&[]).is_empty());
+
+  // the day 7 feedback loop example, minimizing instead
+  let mut computer = Computer::new();
+  computer.load(
+    "3,26,1001,26,-4,26,3,27,1002,27,2,27,1,27,26,27,4,27,1001,28,-1,28,\
+     1005,28,6,99,0,0,5",
+  );
+  let optimizer = Optimizer::new(computer, Topology::Loop, |outputs: &[i64]| {
+    outputs.last().map(|&thrust| -thrust)
+  });
+  let best = optimizer.best(&[5, 6, 7, 8, 9]).unwrap();
+  let ranked = optimizer.ranked(&[5, 6, 7, 8, 9]);
+  assert_eq!(ranked.last().unwrap().phases, vec![9, 8, 7, 6, 5]);
+  assert_eq!(ranked.last().unwrap().score, -139629729);
+  assert_eq!(best, ranked[0]);
+  // orderings that never settle are left out instead of hanging
+  let mut computer = Computer::new();
+  computer.load("3,9,1008,9,1,10,1005,10,6,99,0,0");
+  let mut optimizer = Optimizer::new(computer, Topology::Chain, |_: &[i64]| Some(0));
+  optimizer.set_budget(1_000);
+  let ranked = optimizer.ranked(&[0, 1]);
+  assert!(ranked.is_empty());
+}
+
+#[test]
+fn test_word_types() {
+  // squares the input and outputs the result
+  let program = "3,9,2,9,9,10,4,10,99,0,0";
+
+  let mut computer = Computer::<i32>::default();
+  computer.load(program);
+  computer.input(50_000);
+  assert!(matches!(computer.run(), Interruption::Output));
+  assert_eq!(computer.output(), Some(50_000i32.wrapping_mul(50_000)));
+
+  let mut computer = Computer::<i32>::default();
+  computer.set_arithmetic(Arithmetic::Checked);
+  computer.load(program);
+  computer.input(50_000);
+  match computer.run() {
+    Interruption::Fault(Fault::Overflow { pc, .. }) => assert_eq!(pc, 2),
+    interruption => panic!("unexpected interruption: {:?}", interruption),
+  }
+
+  let mut computer = Computer::<i64>::default();
+  computer.set_arithmetic(Arithmetic::Checked);
+  computer.set_engine(Engine::Cached);
+  computer.load(program);
+  computer.input(5_000_000_000);
+  match computer.run() {
+    Interruption::Fault(fault) => assert_eq!(
+      fault.to_string(),
+      "arithmetic overflow (pc: 2, fp: 0, instruction: 2)"
+    ),
+    interruption => panic!("unexpected interruption: {:?}", interruption),
+  }
+
+  let mut computer = Computer::<i128>::default();
+  computer.set_arithmetic(Arithmetic::Checked);
+  computer.load(program);
+  computer.input(5_000_000_000);
+  assert!(matches!(computer.run(), Interruption::Output));
+  assert_eq!(computer.output(), Some(25_000_000_000_000_000_000));
+
+  let mut computer = Computer::<BigInt>::default();
+  computer.set_arithmetic(Arithmetic::Checked);
+  computer.load(program);
+  computer.input("123456789012345678901234567890".parse().unwrap());
+  assert!(matches!(computer.run(), Interruption::Output));
+  assert_eq!(
+    computer.output().unwrap().to_string(),
+    "15241578753238836750495351562536198787501905199875019052100"
+  );
+
+  // input pulled from a source is narrowed the same way
+  let mut computer = Computer::<i32>::default();
+  computer.load(program);
+  computer.set_input_source(Box::new(IterInput(vec![1 << 32 | 3].into_iter())));
+  assert!(matches!(computer.run(), Interruption::Output));
+  assert_eq!(computer.output(), Some(9));
+
+  let mut computer = Computer::<i32>::default();
+  computer.set_arithmetic(Arithmetic::Checked);
+  computer.load(program);
+  computer.set_input_source(Box::new(IterInput(vec![1 << 32 | 3].into_iter())));
+  match computer.run() {
+    Interruption::Fault(fault) => assert_eq!(
+      fault.to_string(),
+      "arithmetic overflow (pc: 0, fp: 0, instruction: 3)"
+    ),
+    interruption => panic!("unexpected interruption: {:?}", interruption),
+  }
+}
+
+#[test]
+fn test_big_words_in_code() {
+  // an immediate too big for an i64, on both engines
+  let program = "1101,100000000000000000000,1,9,4,9,99,0,0,0";
+
+  for &engine in [Engine::Interpreter, Engine::Cached].iter() {
+    let mut computer = Computer::<BigInt>::default();
+    computer.set_engine(engine);
+    computer.load(program);
+    assert!(matches!(computer.run(), Interruption::Output));
+    assert_eq!(
+      computer.output().unwrap().to_string(),
+      "100000000000000000001"
+    );
+  }
+
+  // a huge opcode is just an unknown one, not whatever it'd be clamped to
+  let mut computer = Computer::<BigInt>::default();
+  computer.load("100000000000000000007,0,0,0");
+  assert!(matches!(
+    computer.run(),
+    Interruption::Fault(Fault::UnknownOpcode { .. })
+  ));
+}
+
+#[test]
+fn test_plugin_opcodes() {
+  // dbg [11]; pow #2, #10 -> [11]; dbg [11]; out [11]; then the unknown
+  // opcode 12
+  let program = "10,11,1111,2,10,11,10,11,4,11,12,4";
+  let printed = Arc::new(Mutex::new(Vec::new()));
+
+  for &engine in [Engine::Interpreter, Engine::Cached].iter() {
+    let log = printed.clone();
+    let mut computer = Computer::new();
+    computer.set_engine(engine);
+    computer
+      .plugins_mut()
+      .register(10, "dbg", &[Access::Read], move |values: &[i64]| {
+        log.lock().unwrap().push(values[0]);
+        Effect::Continue
+      });
+    computer.plugins_mut().register(
+      11,
+      "pow",
+      &[Access::Read, Access::Read, Access::Write],
+      |values: &[i64]| Effect::Store(values[0].pow(values[1] as u32)),
+    );
+    computer.load(program);
+
+    // clones keep the plug-ins
+    let mut computer = computer.clone();
+    assert!(matches!(computer.run(), Interruption::Output));
+    assert_eq!(computer.output(), Some(1024));
+    match computer.run() {
+      Interruption::Fault(Fault::UnknownOpcode { pc, .. }) => assert_eq!(pc, 10),
+      interruption => panic!("unexpected interruption: {:?}", interruption),
+    }
+  }
+
+  assert_eq!(*printed.lock().unwrap(), vec![4, 1024, 4, 1024]);
+
+  // write parameters still can't be immediate
+  let mut computer = Computer::new();
+  computer.plugins_mut().register(
+    11,
+    "pow",
+    &[Access::Read, Access::Read, Access::Write],
+    |values: &[i64]| Effect::Store(values[0].pow(values[1] as u32)),
+  );
+  computer.load("11111,2,10,9,99");
+  assert!(matches!(
+    computer.run(),
+    Interruption::Fault(Fault::WriteInImmediateMode { .. })
+  ));
+
+  // storing needs somewhere to store to
+  let mut computer = Computer::new();
+  computer
+    .plugins_mut()
+    .register(12, "bad", &[Access::Read], |values: &[i64]| {
+      Effect::Store(values[0])
+    });
+  computer.load("1112,5,99");
+  match computer.run() {
+    Interruption::Fault(fault) => assert_eq!(
+      fault.to_string(),
+      "store without a write parameter (pc: 0, fp: 0, instruction: 1112)"
+    ),
+    interruption => panic!("unexpected interruption: {:?}", interruption),
+  }
+
+  // the widest plug-in there's room for still decodes its last mode
+  let mut computer = Computer::new();
+  computer
+    .plugins_mut()
+    .register(12, "wide", &[Access::Read; 16], |values: &[i64]| {
+      Effect::Output(values[15])
+    });
+  computer.load("111111111111111112,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,7,99");
+  assert!(matches!(computer.run(), Interruption::Output));
+  assert_eq!(computer.output(), Some(7));
+}
+
+#[test]
+#[should_panic(expected = "opcode 12 has more than 16 parameters")]
+fn test_plugin_arity_limit() {
+  Computer::new()
+    .plugins_mut()
+    .register(12, "wider", &[Access::Read; 17], |_: &[i64]| {
+      Effect::Continue
+    });
+}
+
+#[test]
+fn test_flow_graph() {
+  //  0: add #1, #1 -> [11]   ; rewrites the operand of the out below
+  //  4: jt [11], #10
+  //  7: hlt
+  //  8: data 0, 0
+  // 10: out #0
+  // 12: jf #0, #4
+  // 15: jt #1, [0]           ; unreachable
+  let graph = analyze(&parse(
+    "1101,1,1,11,1005,11,10,99,0,0,104,0,1106,0,4,105,1,0",
+  ));
+
+  let starts: Vec<u64> = graph.blocks.keys().copied().collect();
+  assert_eq!(starts, vec![0, 4, 7, 10]);
+  assert_eq!(graph.blocks[&0].exit, Exit::Next);
+  assert_eq!(graph.blocks[&4].exit, Exit::Jump);
+  assert_eq!(graph.blocks[&7].exit, Exit::Halt);
+  assert_eq!(graph.blocks[&10].instructions.len(), 2);
+  assert_eq!(graph.block_at(13).map(|block| block.start), Some(10));
+  assert_eq!(graph.block_at(8), None);
+
+  let edges: Vec<(u64, u64, EdgeKind)> = graph
+    .edges
+    .iter()
+    .map(|edge| (edge.from, edge.to, edge.kind))
+    .collect();
+  assert_eq!(
+    edges,
+    vec![
+      (0, 4, EdgeKind::Next),
+      (4, 10, EdgeKind::Taken),
+      (4, 7, EdgeKind::NotTaken),
+      (10, 4, EdgeKind::Taken),
+    ]
+  );
+
+  assert_eq!(
+    graph.modifications,
+    vec![Modification {
+      writer: 0,
+      instruction: 10,
+      address: 11
+    }]
+  );
+
+  let dot = graph.to_dot();
+  assert!(dot.contains("  L10 [label=\"L10:\\l    10: out #0  ; modified by 0\\l"));
+  assert!(dot.contains("  L4 -> L7 [label=\"not taken\", style=dashed];\n"));
+
+  // indirect jumps and words that don't decode end their blocks
+  let graph = analyze(&parse("1106,0,5,0,0,1105,1,8,105,1,0"));
+  assert_eq!(graph.blocks[&0].exit, Exit::Jump);
+  assert_eq!(graph.blocks[&5].exit, Exit::Jump);
+  assert_eq!(graph.blocks[&8].exit, Exit::Indirect);
+  assert!(!graph.is_self_modifying());
+  let graph = analyze(&parse("1,0,0,0,42"));
+  assert_eq!(graph.blocks[&0].exit, Exit::Invalid);
+  assert_eq!(graph.blocks[&0].end(), 4);
+}
+
+#[test]
+fn test_decompile() {
+  let program = assemble(
+    "\
+      arb #100
+      add #3, #0 -> [count]
+    loop:
+      out [count]
+      add [count], #-1 -> [count]
+      lt #0, [count] -> [flag]
+      jt [flag], #loop
+      add #12, #0 -> [rb+1]
+      add #back, #0 -> [rb+0]
+      jt #1, #square
+    back:
+      out [rb+1]
+      hlt
+    square:
+      arb #3
+      mul [rb-2], [rb-2] -> [rb-1]
+      lt [rb-1], #100 -> [flag]
+      jt [flag], #small
+      add #100, #0 -> [rb-1]
+    small:
+      add [rb-1], #0 -> [rb-2]
+      arb #-3
+      jt #1, [rb+0]
+    count:
+      data 0
+    flag:
+      data 0
+",
+  )
+  .unwrap();
+
+  let expected = "\
+fn main() {
+  rb += 100;
+  g59 = 3;
+  do {
+    output(g59);
+    g59 = g59 - 1;
+  } while (0 < g59);
+  f33(12);
+  output(out1);
+  halt();
+}
+
+fn f33(arg1) {
+  let local1;
+  local1 = arg1 * arg1;
+  if (local1 >= 100) {
+    local1 = 100;
+  }
+  arg1 = local1;
+  return;
+}
+";
+  assert_eq!(decompile(&program), expected);
+
+  // negative targets fault when taken, so they get no label
+  let program = assemble(
+    "\
+      in -> [x]
+      jt [x], #-1
+      jt #1, #-2
+    x:
+      data 0
+",
+  )
+  .unwrap();
+  assert_eq!(
+    decompile(&program),
+    "\
+fn main() {
+  g8 = input();
+  if (g8 != 0) goto *-1;
+  goto *-2;
+}
+"
+  );
+}
+
+#[test]
+fn test_fuzzer() {
+  let mut fuzzer = Fuzzer::new(1);
+  assert!(fuzzer.fuzz(300).is_empty());
+
+  // relative addresses that overflow fault the same way on every engine
+  let case = Case {
+    program: vec![109, -9223372036854775807, 2206, -2],
+    inputs: vec![],
+  };
+  assert!(fuzzer.check(&case).is_none());
+
+  // an implementation that loses every 7 it outputs is caught, down to a
+  // program that just outputs one
+  let reference = Fuzzer::new(1);
+  fuzzer.add_implementation(
+    "lossy",
+    Box::new(move |case: &Case| {
+      let (_, mut run) = reference.run(case).remove(0);
+      run.outputs.retain(|&value| value != 7);
+      run
+    }),
+  );
+
+  let discrepancies = fuzzer.fuzz(300);
+  assert!(!discrepancies.is_empty());
+  for discrepancy in discrepancies.iter() {
+    assert!(discrepancy.case.program.len() <= 3, "{}", discrepancy);
+    let (_, run) = &discrepancy.runs[0];
+    assert_eq!(run.outputs, vec![7]);
+  }
+}
+
+#[test]
+fn test_conformance() {
+  fn computer<W: Word>(example: &Example, engine: Engine) -> Box<dyn Machine> {
+    let mut computer = Computer::<W>::default();
+    computer.set_engine(engine);
+    computer.load(example.program);
+    Box::new(computer)
+  }
+
+  for &engine in [Engine::Interpreter, Engine::Cached].iter() {
+    let failures = check_conformance(|example| computer::<i64>(example, engine));
+    assert!(failures.is_empty(), "{:?}", failures);
+  }
+
+  let failures = check_conformance(|example| computer::<i128>(example, Engine::Cached));
+  assert!(failures.is_empty(), "{:?}", failures);
+  let failures = check_conformance(|example| computer::<BigInt>(example, Engine::Interpreter));
+  assert!(failures.is_empty(), "{:?}", failures);
+
+  // a machine that can't compare is caught
+  let failures = check_conformance(|example| {
+    let mut computer = Computer::new();
+    computer.load(&example.program.replace("1108", "1107"));
+    Box::new(computer)
+  });
+  let names: Vec<&str> = failures.iter().map(|failure| failure.example).collect();
+  assert_eq!(
+    names,
+    vec![
+      "day05 equal to 8, immediate mode",
+      "day05 equal to 8, immediate mode",
+      "day05 not equal to 8, immediate mode",
+    ]
+  );
+}
@@ -0,0 +1,253 @@
+use crate::fault::Fault;
+use crate::instruction::{Instruction, Opcode, Parameter};
+use std::env;
+use std::fmt;
+use std::fs::File;
+use std::io;
+use std::io::{BufWriter, Write};
+
+// Everything the computer knows about one executed instruction. `addresses`
+// holds the resolved address of every operand (`None` for immediates) and
+// `values` what each operand held right before the instruction executed.
+// Instructions that fault are traced too, with the fault and no write.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct TraceEvent {
+  pub pc: u64,
+  pub fp: i64,
+  pub operation: Operation,
+  pub addresses: Vec<Option<u64>>,
+  pub values: Vec<i64>,
+  pub write: Option<(u64, i64)>,
+  pub fault: Option<Fault>,
+}
+
+// What was at pc: a built-in instruction, a plug-in opcode, or a word that
+// is neither, which can only have faulted.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub enum Operation {
+  Instruction(Instruction),
+  Plugin {
+    name: String,
+    params: Vec<Parameter>,
+    write_parameter: Option<usize>,
+  },
+  Invalid(i64),
+}
+
+impl Operation {
+  pub fn mnemonic(&self) -> &str {
+    match self {
+      Operation::Instruction(instruction) => instruction.opcode.mnemonic(),
+      Operation::Plugin { name, .. } => name,
+      Operation::Invalid(_) => "data",
+    }
+  }
+
+  pub fn params(&self) -> &[Parameter] {
+    match self {
+      Operation::Instruction(instruction) => &instruction.params,
+      Operation::Plugin { params, .. } => params,
+      Operation::Invalid(_) => &[],
+    }
+  }
+
+  pub fn write_parameter(&self) -> Option<usize> {
+    match self {
+      Operation::Instruction(instruction) => instruction.opcode.write_parameter(),
+      Operation::Plugin {
+        write_parameter, ..
+      } => *write_parameter,
+      Operation::Invalid(_) => None,
+    }
+  }
+
+  // Number of words it takes in memory.
+  pub fn size(&self) -> usize {
+    self.params().len() + 1
+  }
+}
+
+impl fmt::Display for Operation {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    match self {
+      Operation::Instruction(instruction) => write!(f, "{}", instruction),
+      Operation::Plugin { .. } => {
+        write!(f, "{}", self.mnemonic())?;
+        let mut separator = " ";
+        for (index, param) in self.params().iter().enumerate() {
+          if Some(index) == self.write_parameter() {
+            write!(f, " -> {}", param)?;
+          } else {
+            write!(f, "{}{}", separator, param)?;
+            separator = ", ";
+          }
+        }
+        Ok(())
+      }
+      Operation::Invalid(word) => write!(f, "data {}", word),
+    }
+  }
+}
+
+pub trait Tracer: Send {
+  fn trace(&mut self, event: &TraceEvent);
+}
+
+pub struct NoopTracer;
+
+impl Tracer for NoopTracer {
+  fn trace(&mut self, _event: &TraceEvent) {}
+}
+
+// Prints one line per instruction, e.g.
+//   12: add [rb+3], #5 -> [100]    ; [rb+3]=7, #5 -> [100]=12
+// with the fault at the end of the line for instructions that faulted.
+pub struct PrettyTracer<W: Write + Send> {
+  out: W,
+}
+
+impl PrettyTracer<io::Stderr> {
+  pub fn stderr() -> PrettyTracer<io::Stderr> {
+    PrettyTracer::new(io::stderr())
+  }
+}
+
+impl<W: Write + Send> PrettyTracer<W> {
+  pub fn new(out: W) -> PrettyTracer<W> {
+    PrettyTracer { out }
+  }
+}
+
+impl<W: Write + Send> Tracer for PrettyTracer<W> {
+  fn trace(&mut self, event: &TraceEvent) {
+    let write_param = event.operation.write_parameter();
+    let mut reads = Vec::new();
+
+    for (index, param) in event.operation.params().iter().enumerate() {
+      if Some(index) != write_param {
+        match param {
+          Parameter::Immediate(_) => reads.push(param.to_string()),
+          _ => reads.push(format!("{}={}", param, event.values[index])),
+        }
+      }
+    }
+
+    let mut effects = reads.join(", ");
+    if let (Some(index), Some((_, value))) = (write_param, event.write) {
+      effects.push_str(&format!(
+        " -> {}={}",
+        event.operation.params()[index],
+        value
+      ));
+    }
+    if let Some(fault) = event.fault {
+      effects.push_str(&format!(" fault: {}", fault));
+    }
+
+    let line = format!(
+      "{:>6}: {:32} ; {}",
+      event.pc,
+      event.operation.to_string(),
+      effects.trim()
+    );
+    writeln!(self.out, "{}", line.trim_end()).expect("Failed to write trace");
+    if event.fault.is_some() {
+      self.out.flush().expect("Failed to write trace");
+    }
+  }
+}
+
+// Writes one JSON object per instruction, so traces can be diffed and fed to
+// other tools. Faulting instructions get a "fault" field. The output is
+// flushed after every fault and halt, since the program may never run again
+// after those.
+pub struct JsonTracer<W: Write + Send> {
+  out: W,
+}
+
+impl JsonTracer<BufWriter<File>> {
+  pub fn create(filename: &str) -> io::Result<JsonTracer<BufWriter<File>>> {
+    Ok(JsonTracer::new(BufWriter::new(File::create(filename)?)))
+  }
+}
+
+impl<W: Write + Send> JsonTracer<W> {
+  pub fn new(out: W) -> JsonTracer<W> {
+    JsonTracer { out }
+  }
+}
+
+impl<W: Write + Send> Tracer for JsonTracer<W> {
+  fn trace(&mut self, event: &TraceEvent) {
+    let join = |items: Vec<String>| items.join(",");
+
+    let operands = join(
+      event
+        .operation
+        .params()
+        .iter()
+        .map(|param| format!("{{\"mode\":{},\"value\":{}}}", param.mode(), param.value()))
+        .collect(),
+    );
+    let addresses = join(
+      event
+        .addresses
+        .iter()
+        .map(|addr| match addr {
+          Some(addr) => addr.to_string(),
+          None => String::from("null"),
+        })
+        .collect(),
+    );
+    let values = join(event.values.iter().map(|v| v.to_string()).collect());
+    let write = match event.write {
+      Some((addr, value)) => format!("{{\"addr\":{},\"value\":{}}}", addr, value),
+      None => String::from("null"),
+    };
+
+    let fault = match event.fault {
+      Some(fault) => format!(",\"fault\":\"{}\"", fault),
+      None => String::new(),
+    };
+
+    writeln!(
+      self.out,
+      "{{\"pc\":{},\"fp\":{},\"opcode\":\"{}\",\"operands\":[{}],\"addresses\":[{}],\"values\":[{}],\"write\":{}{}}}",
+      event.pc,
+      event.fp,
+      escape(event.operation.mnemonic()),
+      operands,
+      addresses,
+      values,
+      write,
+      fault
+    )
+    .expect("Failed to write trace");
+
+    let halted = match &event.operation {
+      Operation::Instruction(instruction) => instruction.opcode == Opcode::Halt,
+      _ => false,
+    };
+    if halted || event.fault.is_some() {
+      self.out.flush().expect("Failed to write trace");
+    }
+  }
+}
+
+fn escape(text: &str) -> String {
+  text.replace('\\', "\\\\").replace('"', "\\\"")
+}
+
+// Tracer selected with the INTCODE_TRACE environment variable: `-` prints a
+// human-readable trace to stderr, anything else is a file to write JSON
+// lines to.
+pub fn tracer_from_env() -> Option<Box<dyn Tracer>> {
+  match env::var("INTCODE_TRACE") {
+    Ok(ref target) if target.is_empty() => None,
+    Ok(ref target) if target == "-" => Some(Box::new(PrettyTracer::stderr())),
+    Ok(filename) => Some(Box::new(
+      JsonTracer::create(&filename).expect("Failed to create trace file"),
+    )),
+    Err(_) => None,
+  }
+}
@@ -0,0 +1,304 @@
+use crate::computer::{Computer, Interruption, MEMORY_LIMIT};
+use crate::fault::Fault;
+use std::env;
+use std::fmt;
+use std::fs;
+use std::fs::File;
+use std::io;
+use std::io::{BufWriter, Write};
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+const MAGIC: &str = "intcode-transcript";
+const VERSION: u32 = 2;
+
+// One value passed to `Computer::input`, returned by `Computer::output` or
+// patched into memory with `Computer::write`, along with how many
+// instructions the machine had executed at the time.
+#[derive(Debug, PartialEq, Eq, Copy, Clone)]
+pub enum Entry {
+  Input {
+    value: i64,
+    executed: u64,
+  },
+  Output {
+    value: i64,
+    executed: u64,
+  },
+  Write {
+    address: u64,
+    value: i64,
+    executed: u64,
+  },
+}
+
+impl fmt::Display for Entry {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    match *self {
+      Entry::Input { value, executed } => write!(f, "in {} {}", value, executed),
+      Entry::Output { value, executed } => write!(f, "out {} {}", value, executed),
+      Entry::Write {
+        address,
+        value,
+        executed,
+      } => write!(f, "write {} {} {}", address, value, executed),
+    }
+  }
+}
+
+// Every input, output and memory patch of an interactive session, in the
+// order the caller exchanged them with the machine. The file is the header
+// line followed by one entry per line:
+//
+//   intcode-transcript 2
+//   write 0 2 0
+//   in 1 0
+//   out 42 1534
+//
+// Version 1 transcripts, which predate patches, are still read.
+#[derive(Debug, PartialEq, Eq, Clone, Default)]
+pub struct Transcript {
+  pub entries: Vec<Entry>,
+}
+
+#[derive(Debug)]
+pub enum TranscriptError {
+  Io(io::Error),
+  UnsupportedVersion(u32),
+  Invalid { line: usize, message: String },
+}
+
+impl fmt::Display for TranscriptError {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    match self {
+      TranscriptError::Io(err) => write!(f, "{}", err),
+      TranscriptError::UnsupportedVersion(version) => {
+        write!(f, "unsupported transcript version {}", version)
+      }
+      TranscriptError::Invalid { line, message } => write!(f, "line {}: {}", line, message),
+    }
+  }
+}
+
+impl From<io::Error> for TranscriptError {
+  fn from(err: io::Error) -> TranscriptError {
+    TranscriptError::Io(err)
+  }
+}
+
+fn invalid<T>(line: usize, message: String) -> Result<T, TranscriptError> {
+  Err(TranscriptError::Invalid { line, message })
+}
+
+impl Transcript {
+  pub fn new() -> Transcript {
+    Transcript::default()
+  }
+
+  pub fn open(filename: &str) -> Result<Transcript, TranscriptError> {
+    Transcript::decode(&fs::read_to_string(filename)?)
+  }
+
+  pub fn save(&self, filename: &str) -> Result<(), TranscriptError> {
+    fs::write(filename, self.encode())?;
+    Ok(())
+  }
+
+  pub fn encode(&self) -> String {
+    let mut text = format!("{} {}\n", MAGIC, VERSION);
+    for entry in self.entries.iter() {
+      text.push_str(&format!("{}\n", entry));
+    }
+    text
+  }
+
+  pub fn decode(text: &str) -> Result<Transcript, TranscriptError> {
+    let mut lines = text.lines().enumerate().map(|(i, line)| (i + 1, line));
+
+    let version = match lines.next() {
+      Some((number, header)) => match header.strip_prefix(MAGIC) {
+        Some(version) => version
+          .trim()
+          .parse()
+          .or_else(|_| invalid(number, format!("invalid version '{}'", version.trim())))?,
+        None => return invalid(number, String::from("not an intcode transcript")),
+      },
+      None => return invalid(1, String::from("empty transcript")),
+    };
+    if !(1..=VERSION).contains(&version) {
+      return Err(TranscriptError::UnsupportedVersion(version));
+    }
+
+    let mut transcript = Transcript::new();
+    for (number, line) in lines {
+      let words: Vec<&str> = line.split_whitespace().collect();
+      let value = |value: &str| {
+        value
+          .parse::<i64>()
+          .or_else(|_| invalid(number, format!("invalid value '{}'", value)))
+      };
+      let count = |executed: &str| {
+        executed
+          .parse::<u64>()
+          .or_else(|_| invalid(number, format!("invalid count '{}'", executed)))
+      };
+
+      let entry = match words.as_slice() {
+        [] => continue,
+        ["write", address, written, executed] => {
+          let address = match address.parse() {
+            Ok(address) if address < MEMORY_LIMIT => address,
+            _ => return invalid(number, format!("invalid address '{}'", address)),
+          };
+          Entry::Write {
+            address,
+            value: value(written)?,
+            executed: count(executed)?,
+          }
+        }
+        [kind, read, executed] => {
+          let value = value(read)?;
+          let executed = count(executed)?;
+
+          match *kind {
+            "in" => Entry::Input { value, executed },
+            "out" => Entry::Output { value, executed },
+            _ => return invalid(number, format!("unknown entry '{}'", kind)),
+          }
+        }
+        _ => return invalid(number, format!("invalid entry '{}'", line.trim())),
+      };
+      transcript.entries.push(entry);
+    }
+
+    Ok(transcript)
+  }
+}
+
+// Writes a transcript as the session goes, flushing every entry, so it
+// survives a run that never finishes cleanly.
+pub struct Recorder {
+  out: Box<dyn Write + Send>,
+}
+
+impl Recorder {
+  pub fn new(mut out: Box<dyn Write + Send>) -> Recorder {
+    writeln!(out, "{} {}", MAGIC, VERSION).expect("Failed to write transcript");
+    out.flush().expect("Failed to write transcript");
+    Recorder { out }
+  }
+
+  pub fn create(filename: &str) -> io::Result<Recorder> {
+    Ok(Recorder::new(Box::new(BufWriter::new(File::create(
+      filename,
+    )?))))
+  }
+
+  pub fn record(&mut self, entry: Entry) {
+    writeln!(self.out, "{}", entry).expect("Failed to write transcript");
+    self.out.flush().expect("Failed to write transcript");
+  }
+}
+
+// Number of recorders `recorder_from_env` has handed out so far.
+static RECORDERS: AtomicUsize = AtomicUsize::new(0);
+
+// Recorder selected with the INTCODE_RECORD environment variable, naming the
+// transcript file to write. Every machine asking for one gets its own file:
+// the first one the name as given, the next ones with `.1`, `.2`, ...
+// appended.
+pub fn recorder_from_env() -> Option<Recorder> {
+  match env::var("INTCODE_RECORD") {
+    Ok(ref filename) if !filename.is_empty() => {
+      let filename = match RECORDERS.fetch_add(1, Ordering::SeqCst) {
+        0 => filename.clone(),
+        index => format!("{}.{}", filename, index),
+      };
+      Some(Recorder::create(&filename).expect("Failed to create transcript file"))
+    }
+    _ => None,
+  }
+}
+
+// What the replaying machine did instead of producing the expected output.
+#[derive(Debug, PartialEq, Eq, Copy, Clone)]
+pub enum Actual {
+  Output(i64),
+  WaitingForInput,
+  Halted,
+  Faulted(Fault),
+}
+
+#[derive(Debug, PartialEq, Eq, Copy, Clone)]
+pub struct Divergence {
+  // index of the first transcript entry that didn't match
+  pub entry: usize,
+  pub expected: i64,
+  pub recorded_at: u64,
+  pub actual: Actual,
+  pub executed: u64,
+}
+
+impl fmt::Display for Divergence {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    write!(
+      f,
+      "entry {}: expected output {} (after {} instructions), ",
+      self.entry, self.expected, self.recorded_at
+    )?;
+
+    match self.actual {
+      Actual::Output(value) => write!(f, "got {}", value)?,
+      Actual::WaitingForInput => write!(f, "machine is waiting for input")?,
+      Actual::Halted => write!(f, "machine halted")?,
+      Actual::Faulted(fault) => write!(f, "machine faulted: {}", fault)?,
+    }
+
+    write!(f, " (after {} instructions)", self.executed)
+  }
+}
+
+// Drives `computer`, freshly loaded with the recorded program, through the
+// transcript: inputs and memory patches are applied as they come and every
+// recorded output has to match what the machine outputs next. Returns the
+// number of entries replayed, or the first point where the machine diverged.
+pub fn replay(computer: &mut Computer, transcript: &Transcript) -> Result<usize, Divergence> {
+  for (index, entry) in transcript.entries.iter().enumerate() {
+    let (expected, recorded_at) = match *entry {
+      Entry::Input { value, .. } => {
+        computer.input(value);
+        continue;
+      }
+      Entry::Write { address, value, .. } => {
+        computer
+          .write(address, value)
+          .expect("Failed to replay a write");
+        continue;
+      }
+      Entry::Output { value, executed } => (value, executed),
+    };
+
+    let actual = if computer.pending_output().is_empty() {
+      match computer.run() {
+        Interruption::Output => Actual::Output(computer.output().unwrap()),
+        Interruption::Input => Actual::WaitingForInput,
+        Interruption::Halt => Actual::Halted,
+        Interruption::Fault(fault) => Actual::Faulted(fault),
+        Interruption::BudgetExhausted => unreachable!(),
+      }
+    } else {
+      Actual::Output(computer.output().unwrap())
+    };
+
+    if actual != Actual::Output(expected) {
+      return Err(Divergence {
+        entry: index,
+        expected,
+        recorded_at,
+        actual,
+        executed: computer.executed(),
+      });
+    }
+  }
+
+  Ok(transcript.entries.len())
+}
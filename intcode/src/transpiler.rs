@@ -0,0 +1,480 @@
+use crate::computer::MEMORY_LIMIT;
+use crate::flow::{analyze_from, constant_result, Block, Exit, FlowGraph};
+use crate::instruction::{Instruction, Opcode, Parameter};
+use std::collections::BTreeSet;
+use std::fmt::Write;
+
+const WORDS_PER_LINE: usize = 16;
+
+const PRELUDE: &str = r#"
+use intcode::{
+  Arithmetic, Computer, Engine, Fault, Interruption, Machine, Memory, Snapshot, State,
+};
+use std::collections::VecDeque;
+
+pub struct Program {
+  memory: Memory,
+  input_buffer: VecDeque<i64>,
+  output_buffer: VecDeque<i64>,
+  pc: u64,
+  fp: i64,
+  executed: u64,
+  state: State,
+  // set when the host writes into the code; the next run interprets
+  code_modified: bool,
+  interpreter: Option<Computer>,
+}
+
+impl Default for Program {
+  fn default() -> Program {
+    Program::new()
+  }
+}
+
+impl Program {
+  pub fn new() -> Program {
+    let mut memory = Memory::new();
+    for (addr, &word) in IMAGE.iter().enumerate() {
+      memory.write(addr as u64, word);
+    }
+
+    Program {
+      memory,
+      input_buffer: VecDeque::new(),
+      output_buffer: VecDeque::new(),
+      pc: 0,
+      fp: 0,
+      executed: 0,
+      state: State::Ready,
+      code_modified: false,
+      interpreter: None,
+    }
+  }
+
+  // Whether the program has been handed over to the interpreter.
+  pub fn is_interpreted(&self) -> bool {
+    self.interpreter.is_some()
+  }
+
+  fn relative(&self, offset: i64) -> Option<u64> {
+    match self.fp.checked_add(offset) {
+      Some(addr) if addr >= 0 && (addr as u64) < MEMORY_LIMIT => Some(addr as u64),
+      _ => None,
+    }
+  }
+
+  // Moves the machine into a `Computer` on first use and runs it from pc,
+  // with the cached engine as writes into the code are what usually bring
+  // it here.
+  fn interpret(&mut self) -> Interruption {
+    if self.interpreter.is_none() {
+      let snapshot = Snapshot {
+        state: self.state,
+        pc: self.pc,
+        fp: self.fp,
+        executed: self.executed,
+        arithmetic: Arithmetic::Wrapping,
+        engine: Engine::Cached,
+        memory: std::mem::take(&mut self.memory),
+        input: std::mem::take(&mut self.input_buffer),
+        output: std::mem::take(&mut self.output_buffer),
+      };
+      self.interpreter = Some(Computer::from_snapshot(snapshot));
+    }
+
+    self.interpreter.as_mut().unwrap().run()
+  }
+}
+"#;
+
+const MACHINE: &str = r#"
+impl Machine for Program {
+  fn input(&mut self, value: i64) {
+    match self.interpreter.as_mut() {
+      Some(computer) => computer.input(value),
+      None => self.input_buffer.push_back(value),
+    }
+  }
+
+  fn output(&mut self) -> Option<i64> {
+    match self.interpreter.as_mut() {
+      Some(computer) => computer.output(),
+      None => self.output_buffer.pop_front(),
+    }
+  }
+
+  fn read(&self, index: u64) -> i64 {
+    match self.interpreter.as_ref() {
+      Some(computer) => computer.read(index),
+      None => self.memory.read(index),
+    }
+  }
+
+  fn write(&mut self, index: u64, value: i64) -> Result<(), Fault> {
+    match self.interpreter.as_mut() {
+      Some(computer) => computer.write(index, value),
+      None if index >= MEMORY_LIMIT => Err(Fault::AddressOutOfRange {
+        pc: self.pc,
+        fp: self.fp,
+        instruction: self.memory.read(self.pc),
+        address: index,
+      }),
+      None => {
+        self.memory.write(index, value);
+        self.code_modified |= is_code(index);
+        Ok(())
+      }
+    }
+  }
+
+  fn get_state(&self) -> State {
+    match self.interpreter.as_ref() {
+      Some(computer) => computer.get_state(),
+      None => self.state,
+    }
+  }
+
+  fn executed(&self) -> u64 {
+    match self.interpreter.as_ref() {
+      Some(computer) => computer.executed(),
+      None => self.executed,
+    }
+  }
+
+  fn run(&mut self) -> Interruption {
+    if self.interpreter.is_some() || self.code_modified {
+      return self.interpret();
+    }
+
+    self.state = State::Running;
+    loop {
+      match self.pc {
+"#;
+
+const EPILOGUE: &str = r#"        _ => return self.interpret(),
+      }
+    }
+  }
+}
+"#;
+
+// Compiles a program to the source of a Rust module defining `Program`, a
+// `Machine` that runs this image natively: one match arm per basic block,
+// with every operand decoded ahead of time. The module uses the `intcode`
+// crate and is meant to be `include!`d from a build script's output.
+//
+// Whatever the compiled code can't vouch for is handed to a `Computer`
+// built from the machine's state, which interprets from then on: a write
+// into the code, a jump to an address that isn't a compiled block, and any
+// instruction that would fault, so faults read exactly as the
+// interpreter's. Compile an image after patching it, since the host writing
+// into the code also sends the machine to the interpreter.
+pub fn transpile(program: &[i64]) -> String {
+  let graph = explore(program);
+
+  let mut code = BTreeSet::new();
+  for block in graph.blocks.values() {
+    for (address, instruction) in block.instructions.iter() {
+      code.extend(*address..address + instruction.size() as u64);
+    }
+  }
+
+  let mut out = String::new();
+  writeln!(
+    out,
+    "// Generated by intcode::transpile from a {}-word program. Do not edit.",
+    program.len()
+  )
+  .unwrap();
+  out.push_str(PRELUDE);
+
+  writeln!(out, "\nconst MEMORY_LIMIT: u64 = {};", MEMORY_LIMIT).unwrap();
+  write_image(&mut out, program);
+  write_is_code(&mut out, &code);
+
+  out.push_str(MACHINE);
+  for block in graph.blocks.values() {
+    write_block(&mut out, block, &code);
+  }
+  out.push_str(EPILOGUE);
+
+  out
+}
+
+// The flow graph from address 0 and from every code pointer found along the
+// way: constants the program stores that point at an instruction, as in
+// `mul #378, #1 -> [1027]` saving a return address for a later indirect
+// jump.
+fn explore(program: &[i64]) -> FlowGraph {
+  let mut entries = vec![0];
+
+  loop {
+    let graph = analyze_from(program, &entries);
+
+    let mut found = false;
+    for block in graph.blocks.values() {
+      for (_, instruction) in block.instructions.iter() {
+        let target = match constant_result(instruction) {
+          Some(target) if target >= 0 => target as u64,
+          _ => continue,
+        };
+
+        let decodes = program
+          .get(target as usize..)
+          .and_then(Instruction::decode)
+          .is_some();
+        let inside = graph
+          .block_at(target)
+          .is_some_and(|block| !block.instructions.iter().any(|&(start, _)| start == target));
+        if decodes && !inside && !entries.contains(&target) {
+          entries.push(target);
+          found = true;
+        }
+      }
+    }
+
+    if !found {
+      return graph;
+    }
+  }
+}
+
+fn write_image(out: &mut String, program: &[i64]) {
+  writeln!(out, "\nconst IMAGE: [i64; {}] = [", program.len()).unwrap();
+  for line in program.chunks(WORDS_PER_LINE) {
+    let words: Vec<String> = line.iter().map(|word| word.to_string()).collect();
+    writeln!(out, "  {},", words.join(", ")).unwrap();
+  }
+  out.push_str("];\n");
+}
+
+// Every word of every compiled instruction, as ranges.
+fn write_is_code(out: &mut String, code: &BTreeSet<u64>) {
+  let mut ranges: Vec<(u64, u64)> = Vec::new();
+  for &address in code.iter() {
+    match ranges.last_mut() {
+      Some((_, end)) if *end + 1 == address => *end = address,
+      _ => ranges.push((address, address)),
+    }
+  }
+
+  out.push_str("\nfn is_code(addr: u64) -> bool {\n");
+  if ranges.is_empty() {
+    out.push_str("  false\n");
+  } else {
+    let patterns: Vec<String> = ranges
+      .iter()
+      .map(|(start, end)| format!("{}..={}", start, end))
+      .collect();
+    writeln!(out, "  matches!(addr, {})", patterns.join(" | ")).unwrap();
+  }
+  out.push_str("}\n");
+}
+
+// Writes a block as one or more match arms: a new arm starts at each input
+// and halt, and right after each output, so the machine can resume there
+// after interrupting.
+fn write_block(out: &mut String, block: &Block, code: &BTreeSet<u64>) {
+  if block.instructions.is_empty() {
+    return;
+  }
+
+  writeln!(out, "        {} => {{", block.start).unwrap();
+
+  // whether control can reach the instruction being written
+  let mut reachable = true;
+
+  for (index, (address, instruction)) in block.instructions.iter().enumerate() {
+    let resumes_here = match instruction.opcode {
+      Opcode::Input | Opcode::Halt => index > 0,
+      _ => match index.checked_sub(1).map(|i| &block.instructions[i].1) {
+        Some(previous) => previous.opcode == Opcode::Output,
+        None => false,
+      },
+    };
+
+    if resumes_here {
+      if reachable {
+        writeln!(out, "          self.pc = {};", address).unwrap();
+      }
+      writeln!(out, "        }}\n        {} => {{", address).unwrap();
+    } else if !reachable {
+      break;
+    }
+
+    writeln!(out, "          // {}: {}", address, instruction).unwrap();
+    reachable = write_instruction(out, *address, instruction, code);
+  }
+
+  if reachable {
+    match block.exit {
+      Exit::Next => writeln!(out, "          self.pc = {};", block.end()).unwrap(),
+      _ => writeln!(
+        out,
+        "          self.pc = {};\n          return self.interpret();",
+        block.end()
+      )
+      .unwrap(),
+    }
+  }
+  out.push_str("        }\n");
+}
+
+// Writes the code for one instruction. Returns whether control can carry on
+// to the next instruction of the block.
+fn write_instruction(
+  out: &mut String,
+  address: u64,
+  instruction: &Instruction,
+  code: &BTreeSet<u64>,
+) -> bool {
+  let next = address + instruction.size() as u64;
+  let bail = format!("{{ self.pc = {}; return self.interpret(); }}", address);
+  let params = &instruction.params;
+
+  // fixed addresses that are out of bounds fault every time
+  let faults = params.iter().any(|param| match *param {
+    Parameter::Position(addr) => addr < 0 || addr as u64 >= MEMORY_LIMIT,
+    _ => false,
+  });
+  if faults {
+    writeln!(out, "          {}", bail).unwrap();
+    return false;
+  }
+
+  let read = |out: &mut String, index: usize| {
+    let line = match params[index] {
+      Parameter::Immediate(value) => format!("let p{}: i64 = {};", index, value),
+      Parameter::Position(addr) => format!("let p{} = self.memory.read({});", index, addr),
+      Parameter::Relative(offset) => format!(
+        "let p{} = match self.relative({}) {{ Some(addr) => self.memory.read(addr), None => {} }};",
+        index, offset, bail
+      ),
+    };
+    writeln!(out, "          {}", line).unwrap();
+  };
+
+  let write_address = |out: &mut String, index: usize| {
+    let line = match params[index] {
+      Parameter::Relative(offset) => format!(
+        "let addr = match self.relative({}) {{ Some(addr) => addr, None => {} }};",
+        offset, bail
+      ),
+      param => format!("let addr: u64 = {};", param.value()),
+    };
+    writeln!(out, "          {}", line).unwrap();
+  };
+
+  // after a store: a write into the code leaves the rest to the interpreter
+  let check_store = |out: &mut String, index: usize| match params[index] {
+    Parameter::Relative(_) => {
+      writeln!(
+        out,
+        "          if is_code(addr) {{ self.pc = {}; return self.interpret(); }}",
+        next
+      )
+      .unwrap();
+      true
+    }
+    param if code.contains(&(param.value() as u64)) => {
+      writeln!(
+        out,
+        "          self.pc = {};\n          return self.interpret();",
+        next
+      )
+      .unwrap();
+      false
+    }
+    _ => true,
+  };
+
+  match instruction.opcode {
+    Opcode::Add | Opcode::Multiply | Opcode::LessThan | Opcode::Equals => {
+      read(out, 0);
+      read(out, 1);
+      write_address(out, 2);
+      let value = match instruction.opcode {
+        Opcode::Add => "p0.wrapping_add(p1)",
+        Opcode::Multiply => "p0.wrapping_mul(p1)",
+        Opcode::LessThan => "(p0 < p1) as i64",
+        _ => "(p0 == p1) as i64",
+      };
+      writeln!(out, "          self.memory.write(addr, {});", value).unwrap();
+      out.push_str("          self.executed += 1;\n");
+      check_store(out, 2)
+    }
+    Opcode::Input => {
+      writeln!(
+        out,
+        "          if self.input_buffer.is_empty() {{ self.pc = {}; self.state = State::Interrupted; return Interruption::Input; }}",
+        address
+      )
+      .unwrap();
+      write_address(out, 0);
+      out.push_str(
+        "          let value = self.input_buffer.pop_front().expect(\"Error reading input buffer\");\n",
+      );
+      out.push_str("          self.memory.write(addr, value);\n");
+      out.push_str("          self.executed += 1;\n");
+      check_store(out, 0)
+    }
+    Opcode::Output => {
+      read(out, 0);
+      writeln!(out, "          self.pc = {};", next).unwrap();
+      out.push_str("          self.executed += 1;\n");
+      out.push_str("          self.output_buffer.push_back(p0);\n");
+      out.push_str("          self.state = State::Interrupted;\n");
+      out.push_str("          return Interruption::Output;\n");
+      false
+    }
+    Opcode::JumpIfTrue | Opcode::JumpIfFalse => {
+      read(out, 0);
+      if !matches!(params[1], Parameter::Immediate(_)) {
+        read(out, 1);
+      }
+      let condition = match instruction.opcode {
+        Opcode::JumpIfTrue => "p0 != 0",
+        _ => "p0 == 0",
+      };
+      writeln!(out, "          if {} {{", condition).unwrap();
+      match params[1] {
+        Parameter::Immediate(target) if target < 0 => {
+          writeln!(out, "            {}", bail).unwrap();
+        }
+        Parameter::Immediate(target) => {
+          writeln!(out, "            self.pc = {};", target).unwrap();
+        }
+        _ => {
+          writeln!(out, "            if p1 < 0 {}", bail).unwrap();
+          out.push_str("            self.pc = p1 as u64;\n");
+        }
+      }
+      writeln!(
+        out,
+        "          }} else {{\n            self.pc = {};\n          }}",
+        next
+      )
+      .unwrap();
+      out.push_str("          self.executed += 1;\n");
+      false
+    }
+    Opcode::AdjustRelativeBase => {
+      read(out, 0);
+      writeln!(
+        out,
+        "          self.fp = match self.fp.checked_add(p0) {{ Some(fp) => fp, None => {} }};",
+        bail
+      )
+      .unwrap();
+      out.push_str("          self.executed += 1;\n");
+      true
+    }
+    Opcode::Halt => {
+      writeln!(out, "          self.pc = {};", address).unwrap();
+      out.push_str("          self.executed += 1;\n");
+      out.push_str("          self.state = State::Halted;\n");
+      out.push_str("          return Interruption::Halt;\n");
+      false
+    }
+  }
+}
@@ -0,0 +1,100 @@
+use num_bigint::BigInt;
+use num_traits::ToPrimitive;
+use std::convert::TryFrom;
+use std::fmt;
+use std::str::FromStr;
+
+// The integer type a computer keeps in memory. Addresses, the relative base
+// and everything the computer hands to tracers, recorders, profiles and I/O
+// connections stay i64; words that don't fit are clamped on the way.
+pub trait Word:
+  Clone + Ord + Default + fmt::Debug + fmt::Display + FromStr + Send + Sync + 'static
+{
+  // Wraps around if `value` doesn't fit.
+  fn from_i64(value: i64) -> Self;
+  // `None` if `value` doesn't fit.
+  fn checked_from_i64(value: i64) -> Option<Self>;
+  fn to_i64(&self) -> Option<i64>;
+  fn checked_add(&self, other: &Self) -> Option<Self>;
+  fn checked_mul(&self, other: &Self) -> Option<Self>;
+  fn wrapping_add(&self, other: &Self) -> Self;
+  fn wrapping_mul(&self, other: &Self) -> Self;
+}
+
+macro_rules! primitive_word {
+  ($t:ty) => {
+    impl Word for $t {
+      fn from_i64(value: i64) -> $t {
+        value as $t
+      }
+
+      fn checked_from_i64(value: i64) -> Option<$t> {
+        <$t>::try_from(value).ok()
+      }
+
+      fn to_i64(&self) -> Option<i64> {
+        i64::try_from(*self).ok()
+      }
+
+      fn checked_add(&self, other: &$t) -> Option<$t> {
+        <$t>::checked_add(*self, *other)
+      }
+
+      fn checked_mul(&self, other: &$t) -> Option<$t> {
+        <$t>::checked_mul(*self, *other)
+      }
+
+      fn wrapping_add(&self, other: &$t) -> $t {
+        <$t>::wrapping_add(*self, *other)
+      }
+
+      fn wrapping_mul(&self, other: &$t) -> $t {
+        <$t>::wrapping_mul(*self, *other)
+      }
+    }
+  };
+}
+
+primitive_word!(i32);
+primitive_word!(i64);
+primitive_word!(i128);
+
+// Never overflows, so both arithmetic modes behave the same.
+impl Word for BigInt {
+  fn from_i64(value: i64) -> BigInt {
+    BigInt::from(value)
+  }
+
+  fn checked_from_i64(value: i64) -> Option<BigInt> {
+    Some(BigInt::from(value))
+  }
+
+  fn to_i64(&self) -> Option<i64> {
+    ToPrimitive::to_i64(self)
+  }
+
+  fn checked_add(&self, other: &BigInt) -> Option<BigInt> {
+    Some(self + other)
+  }
+
+  fn checked_mul(&self, other: &BigInt) -> Option<BigInt> {
+    Some(self * other)
+  }
+
+  fn wrapping_add(&self, other: &BigInt) -> BigInt {
+    self + other
+  }
+
+  fn wrapping_mul(&self, other: &BigInt) -> BigInt {
+    self * other
+  }
+}
+
+// The word as an i64, clamped to i64::MIN or i64::MAX if it doesn't fit.
+pub(crate) fn narrow<W: Word>(value: &W) -> i64 {
+  match value.to_i64() {
+    Some(value) => value,
+    None if *value < W::default() => i64::MIN,
+    None => i64::MAX,
+  }
+}